@@ -43,10 +43,18 @@ pub struct AutoBitTiming {
     pub baud_rate: u32,
     /// Sample point in 1/10th of a percent (e.g. 8000 = 80%)
     pub sample_point: u16,
-    /// Synchronization jump width in time quanta
+    /// Synchronization jump width, in the same unit as the sample point
     pub sync_jump_width: u16,
 }
 
+impl AutoBitTiming {
+    /// The calculation needs a non zero baud rate, and a sample point and a synchronization
+    /// jump width within the bit
+    pub(crate) fn is_valid(&self) -> bool {
+        self.baud_rate != 0 && self.sample_point <= 10000 && self.sync_jump_width <= 10000
+    }
+}
+
 pub(super) const NBTP_NBRP_MSK: i32 = 0x1ff;
 pub(super) const NBTP_NTSEG1_MSK: i32 = 0xff;
 pub(super) const NBTP_NTSEG2_MSK: i32 = 0x7f;
@@ -202,7 +210,7 @@ pub(super) fn get_best_sjw(best_tbaud: u32, best_tseg2: u32, sync_jump_width: u1
 }
 
 /// Nominal CAN bit timing
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct NominalBitTiming {
     pub(super) brp: u16,
     pub(super) sjw: u8,
@@ -210,8 +218,19 @@ pub struct NominalBitTiming {
     pub(super) tseg2: u8,
 }
 
+impl NominalBitTiming {
+    /// Check that the fields fit in NBTP and that the synchronization jump width does not
+    /// exceed the phase segment 2
+    pub(crate) fn is_valid(self) -> bool {
+        i32::from(self.brp) <= NBTP_NBRP_MSK
+            && self.tseg1 != 0
+            && i32::from(self.tseg2) <= NBTP_NTSEG2_MSK
+            && self.sjw <= self.tseg2
+    }
+}
+
 /// Data CAN bit timing
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub struct DataBitTiming {
     pub(super) brp: u8,
     pub(super) sjw: u8,
@@ -219,63 +238,193 @@ pub struct DataBitTiming {
     pub(super) tseg2: u8,
 }
 
-pub(super) fn calculate_bit_timing(
-    module_freq: f32,
-    baud_rate: u32,
-    sample_point: u16,
-    sjw: u16,
-) -> NominalBitTiming {
-    info!(
-        "module_freq: {}, baud_rate: {}, sample_point: {}, sync_jump_with: {}",
-        module_freq, baud_rate, sample_point, sjw
-    );
-
-    /* Set values into node */
-    let best = get_best_baud_rate(
+impl DataBitTiming {
+    /// Position of the sample point from the start of the bit, in minimum time quanta (module
+    /// clock periods), saturated to the range of the TDC offset
+    pub(crate) fn sample_point_offset(self) -> u8 {
+        let offset = (u16::from(self.brp) + 1) * (u16::from(self.tseg1) + 2);
+        offset.min(0x7F) as u8
+    }
+
+    /// The transceiver delay compensation only works with a data bit rate prescaler of 1 or 2
+    pub(crate) fn supports_transceiver_delay_compensation(self) -> bool {
+        self.brp <= 1
+    }
+
+    /// Check that the fields fit in DBTP and that the synchronization jump width does not
+    /// exceed the phase segment 2
+    pub(crate) fn is_valid(self) -> bool {
+        i32::from(self.brp) <= DBTP_DBRP_MSK
+            && i32::from(self.tseg1) <= DBTP_DTSEG1_MSK
+            && i32::from(self.tseg2) <= DBTP_DTSEG2_MSK
+            && self.sjw <= self.tseg2
+    }
+}
+
+/// Outcome of an automatic bit timing calculation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct BitTimingReport<T> {
+    /// Values to be written to the bit timing register
+    pub timing: T,
+    /// Achieved bit rate in bps, rounded down
+    pub bit_rate: u32,
+    /// Deviation of the achieved bit rate from the requested one, in parts per million
+    pub bit_rate_error_ppm: i32,
+    /// Achieved sample point, in the same unit as [`AutoBitTiming::sample_point`]
+    pub sample_point: u16,
+    /// Number of time quanta in a bit
+    pub time_quanta_per_bit: u16,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BitTimingError {
+    /// The baud rate is zero, the sample point or the synchronization jump width is not within
+    /// the bit, or no synchronization jump width can be calculated
+    InvalidParameters,
+    /// The closest achievable bit rate deviates from the requested one more than the tolerance
+    ToleranceExceeded { bit_rate_error_ppm: i32 },
+}
+
+/// Bit timing values, in time quanta
+struct Segments {
+    brp: i32,
+    tseg1: i32,
+    tseg2: i32,
+    sjw: u32,
+}
+
+impl Segments {
+    fn calculate(
+        brp_msk: i32,
+        tseg1_msk: i32,
+        tseg2_msk: i32,
+        module_freq: u32,
+        config: &AutoBitTiming,
+    ) -> Result<Self, BitTimingError> {
+        info!(
+            "module_freq: {}, baud_rate: {}, sample_point: {}, sync_jump_with: {}",
+            module_freq, config.baud_rate, config.sample_point, config.sync_jump_width
+        );
+
+        if !config.is_valid() {
+            return Err(BitTimingError::InvalidParameters);
+        }
+
+        let best = get_best_baud_rate(
+            brp_msk,
+            tseg1_msk,
+            tseg2_msk,
+            module_freq as f32,
+            config.baud_rate,
+        );
+
+        let (tseg1, tseg2) =
+            get_best_sample_point(tseg1_msk, tseg2_msk, best.tbaud, config.sample_point);
+        let sjw = get_best_sjw(best.tbaud as u32, tseg2 as u32, config.sync_jump_width);
+        // SJW is written minus one, a zero would wrap around
+        if sjw == 0 {
+            return Err(BitTimingError::InvalidParameters);
+        }
+
+        Ok(Self {
+            brp: best.brp,
+            tseg1,
+            tseg2,
+            sjw,
+        })
+    }
+
+    /// Synchronization jump width as written to the bit timing register, minus one
+    fn sjw_register_value(&self) -> Result<u8, BitTimingError> {
+        u8::try_from(self.sjw)
+            .ok()
+            .and_then(|sjw| sjw.checked_sub(1))
+            .ok_or(BitTimingError::InvalidParameters)
+    }
+
+    /// Time quanta per bit, it may differ from the best tbaud when tseg2 has been forced
+    fn time_quanta_per_bit(&self) -> i32 {
+        1 + self.tseg1 + self.tseg2
+    }
+
+    fn report<T>(
+        &self,
+        timing: T,
+        module_freq: u32,
+        config: &AutoBitTiming,
+        tolerance_ppm: u32,
+    ) -> Result<BitTimingReport<T>, BitTimingError> {
+        let time_quanta_per_bit = self.time_quanta_per_bit();
+        let divider = i64::from(self.brp) * i64::from(time_quanta_per_bit);
+        let requested = i64::from(config.baud_rate) * divider;
+
+        let bit_rate_error_ppm =
+            ((i64::from(module_freq) - requested) * 1_000_000 / requested) as i32;
+
+        if bit_rate_error_ppm.unsigned_abs() > tolerance_ppm {
+            return Err(BitTimingError::ToleranceExceeded { bit_rate_error_ppm });
+        }
+
+        Ok(BitTimingReport {
+            timing,
+            bit_rate: (i64::from(module_freq) / divider) as u32,
+            bit_rate_error_ppm,
+            sample_point: ((1 + self.tseg1) * 10000 / time_quanta_per_bit) as u16,
+            time_quanta_per_bit: time_quanta_per_bit as u16,
+        })
+    }
+}
+
+/// Calculate the nominal bit timing closest to `config` for a module clock of `module_freq` Hz.
+/// An error is returned if the achieved bit rate deviates more than `tolerance_ppm` parts per
+/// million from the requested one.
+pub fn calculate_nominal_bit_timing(
+    module_freq: u32,
+    config: &AutoBitTiming,
+    tolerance_ppm: u32,
+) -> Result<BitTimingReport<NominalBitTiming>, BitTimingError> {
+    let segments = Segments::calculate(
         NBTP_NBRP_MSK,
         NBTP_NTSEG1_MSK,
         NBTP_NTSEG2_MSK,
         module_freq,
-        baud_rate,
-    );
-
-    let (best_tseg1, best_tseg2) =
-        get_best_sample_point(NBTP_NTSEG1_MSK, NBTP_NTSEG2_MSK, best.tbaud, sample_point);
-    let best_sjw = get_best_sjw(best.tbaud as u32, best_tseg2 as u32, sjw);
-
-    NominalBitTiming {
-        brp: best.brp as u16 - 1,
-        sjw: best_sjw as u8 - 1,
-        tseg1: best_tseg1 as u8 - 1,
-        tseg2: best_tseg2 as u8 - 1,
-    }
+        config,
+    )?;
+
+    let timing = NominalBitTiming {
+        brp: segments.brp as u16 - 1,
+        sjw: segments.sjw_register_value()?,
+        tseg1: segments.tseg1 as u8 - 1,
+        tseg2: segments.tseg2 as u8 - 1,
+    };
+
+    segments.report(timing, module_freq, config, tolerance_ppm)
 }
 
-pub(super) fn calculate_fast_bit_timing(
-    module_freq: f32,
-    baud_rate: u32,
-    sample_point: u16,
-    sjw: u16,
-) -> DataBitTiming {
-    /* Set values into node */
-    let best = get_best_baud_rate(
+/// Calculate the data bit timing closest to `config` for a module clock of `module_freq` Hz.
+/// An error is returned if the achieved bit rate deviates more than `tolerance_ppm` parts per
+/// million from the requested one.
+pub fn calculate_data_bit_timing(
+    module_freq: u32,
+    config: &AutoBitTiming,
+    tolerance_ppm: u32,
+) -> Result<BitTimingReport<DataBitTiming>, BitTimingError> {
+    let segments = Segments::calculate(
         DBTP_DBRP_MSK,
         DBTP_DTSEG1_MSK,
         DBTP_DTSEG2_MSK,
         module_freq,
-        baud_rate,
-    );
-
-    let (best_tseg1, best_tseg2) =
-        get_best_sample_point(DBTP_DTSEG1_MSK, DBTP_DTSEG2_MSK, best.tbaud, sample_point);
-    let best_sjw = get_best_sjw(best.tbaud as u32, best_tseg2 as u32, sjw);
-
-    DataBitTiming {
-        brp: best.brp as u8 - 1,
-        sjw: best_sjw as u8 - 1,
-        tseg1: best_tseg1 as u8 - 1,
-        tseg2: best_tseg2 as u8 - 1,
-    }
+        config,
+    )?;
+
+    let timing = DataBitTiming {
+        brp: segments.brp as u8 - 1,
+        sjw: segments.sjw_register_value()?,
+        tseg1: segments.tseg1 as u8 - 1,
+        tseg2: segments.tseg2 as u8 - 1,
+    };
+
+    segments.report(timing, module_freq, config, tolerance_ppm)
 }
 
 #[cfg(test)]
@@ -319,4 +468,106 @@ mod test {
 
         assert_eq!(best_sjw, 1);
     }
+
+    #[test]
+    fn test_calculate_nominal_bit_timing() {
+        let config = AutoBitTiming {
+            baud_rate: 500_000,
+            sample_point: 8000,
+            sync_jump_width: 3,
+        };
+        let report = calculate_nominal_bit_timing(80_000_000, &config, 0).unwrap();
+
+        assert_eq!(
+            report.timing,
+            NominalBitTiming {
+                brp: 7,
+                sjw: 0,
+                tseg1: 14,
+                tseg2: 3,
+            }
+        );
+        assert_eq!(report.bit_rate, 500_000);
+        assert_eq!(report.bit_rate_error_ppm, 0);
+        assert_eq!(report.sample_point, 8000);
+        assert_eq!(report.time_quanta_per_bit, 20);
+    }
+
+    #[test]
+    fn test_calculate_data_bit_timing() {
+        let config = AutoBitTiming {
+            baud_rate: 2_000_000,
+            sample_point: 7500,
+            sync_jump_width: 2000,
+        };
+        let report = calculate_data_bit_timing(80_000_000, &config, 0).unwrap();
+
+        assert_eq!(report.bit_rate, 2_000_000);
+        assert_eq!(report.time_quanta_per_bit, 20);
+        assert_eq!(report.sample_point, 7500);
+        // 15 time quanta before the sample point, two module clocks each
+        assert_eq!(report.timing.sample_point_offset(), 30);
+        assert!(report.timing.supports_transceiver_delay_compensation());
+
+        // A prescaler of 8 is needed at 500 kbit/s
+        let config = AutoBitTiming {
+            baud_rate: 500_000,
+            ..config
+        };
+        let report = calculate_data_bit_timing(80_000_000, &config, 0).unwrap();
+        assert!(!report.timing.supports_transceiver_delay_compensation());
+    }
+
+    #[test]
+    fn test_bit_timing_errors() {
+        let config = AutoBitTiming {
+            baud_rate: 0,
+            sample_point: 8000,
+            sync_jump_width: 3,
+        };
+        assert_eq!(
+            calculate_nominal_bit_timing(80_000_000, &config, 0),
+            Err(BitTimingError::InvalidParameters)
+        );
+
+        // 80 MHz is not a multiple of 3 * 333_333 Hz
+        let config = AutoBitTiming {
+            baud_rate: 333_333,
+            ..config
+        };
+        let report = calculate_nominal_bit_timing(80_000_000, &config, 1000).unwrap();
+        assert_ne!(report.bit_rate_error_ppm, 0);
+        assert!(matches!(
+            calculate_nominal_bit_timing(80_000_000, &config, 0),
+            Err(BitTimingError::ToleranceExceeded { .. })
+        ));
+    }
+
+    #[test]
+    fn test_sync_jump_width_limits() {
+        // A synchronization jump width of the whole bit is limited to the phase segment 2
+        let config = AutoBitTiming {
+            baud_rate: 500_000,
+            sample_point: 8000,
+            sync_jump_width: 10000,
+        };
+        let report = calculate_nominal_bit_timing(80_000_000, &config, 0).unwrap();
+        assert_eq!(report.timing.sjw, report.timing.tseg2);
+
+        let config = AutoBitTiming {
+            sync_jump_width: 10001,
+            ..config
+        };
+        assert_eq!(
+            calculate_nominal_bit_timing(80_000_000, &config, 0),
+            Err(BitTimingError::InvalidParameters)
+        );
+        assert_eq!(
+            calculate_data_bit_timing(80_000_000, &config, 0),
+            Err(BitTimingError::InvalidParameters)
+        );
+
+        // Without phase segment 2 no synchronization jump width is found
+        assert_eq!(get_best_sjw(20, 0, 3), 0);
+    }
 }
@@ -2,19 +2,46 @@
 
 mod service_request;
 
-use super::can_node::{Node, NodeConfig};
+use super::can_node::{ConfigError, Node, NodeConfig};
 use crate::can::NodeId;
 use crate::util::wait_nop_cycles;
 use crate::{pac, scu};
 use core::marker::PhantomData;
 
-pub trait ModuleId {}
+pub trait ModuleId {
+    const INDEX: usize;
+}
 
 pub struct Module0;
-impl ModuleId for Module0 {}
+impl ModuleId for Module0 {
+    const INDEX: usize = 0;
+}
 
 pub struct Module1;
-impl ModuleId for Module1 {}
+impl ModuleId for Module1 {
+    const INDEX: usize = 1;
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TakeNodeError {
+    AlreadyTaken,
+    /// The clock source of the node could not be selected
+    ClockSource,
+    InvalidBitTiming,
+    InvalidRamLayout,
+}
+
+impl From<ConfigError> for TakeNodeError {
+    fn from(value: ConfigError) -> Self {
+        match value {
+            ConfigError::CannotSetClockSource => Self::ClockSource,
+            ConfigError::InvalidBitTiming => Self::InvalidBitTiming,
+            ConfigError::InvalidRamLayout | ConfigError::RamLayoutMismatch => {
+                Self::InvalidRamLayout
+            }
+        }
+    }
+}
 
 // Type states for Module
 pub struct Disabled;
@@ -61,23 +88,35 @@ macro_rules! impl_can_module {
         }
 
         impl Module<$ModuleId, $ModuleReg, Enabled> {
-            /// Take ownership of a CAN node and configure it
-            pub fn take_node<I>(&mut self, node_id: I, config: NodeConfig) -> Option<Node<$($m)::+::N, $ModuleReg, I, crate::can::can_node::Configurable>> where I: NodeId {
+            /// Take ownership of a CAN node and configure it.
+            /// If the configuration fails, the node is not taken and can be taken again.
+            pub fn take_node<I>(&mut self, node_id: I, config: NodeConfig) -> Result<Node<$($m)::+::N, $ModuleReg, I, crate::can::can_node::Configurable>, TakeNodeError> where I: NodeId {
                 let node_index = node_id.as_index();
 
-                #[allow(clippy::indexing_slicing)]
-                let flag : &mut bool = &mut self.nodes_taken[node_index];
-
-                // Check if node is already taken, return None if it is
-                if *flag {
-                    return None;
+                // Check if node is already taken
+                if self.nodes_taken.get(node_index) != Some(&false) {
+                    return Err(TakeNodeError::AlreadyTaken);
                 }
 
+                // Create node
+                let node = Node::<$($m)::+::N, $ModuleReg, I, crate::can::can_node::Configurable>::new(self, node_id, config)?;
+
                 // Mark node as taken
-                *flag = true;
+                if let Some(flag) = self.nodes_taken.get_mut(node_index) {
+                    *flag = true;
+                }
 
-                // Create node
-                Node::<$($m)::+::N, $ModuleReg, I, crate::can::can_node::Configurable>::new(self, node_id, config).ok()
+                Ok(node)
+            }
+
+            /// Give back a node, stopping its bus activity. The node can then be taken again,
+            /// with a different configuration.
+            pub fn release_node<I, S>(&mut self, node: Node<$($m)::+::N, $ModuleReg, I, S>) where I: NodeId {
+                node.release();
+
+                if let Some(flag) = self.nodes_taken.get_mut(I::INDEX) {
+                    *flag = false;
+                }
             }
 
             pub(crate) fn set_clock_source(
@@ -1,8 +1,8 @@
 #![allow(clippy::module_name_repetitions)]
 
 use crate::can::{
-    BitTimingConfig, ClockSource, FastBitTimingConfig, FrameMode, Interrupt, InterruptGroup,
-    InterruptLine, Tos,
+    BitTimingConfig, BusOffRecovery, ClockSource, FastBitTimingConfig, FrameMode, Interrupt,
+    InterruptGroup, InterruptLine, NodeRamLayout, Tos,
 };
 use crate::cpu::Priority;
 
@@ -14,11 +14,131 @@ pub struct NodeInterruptConfig {
     pub tos: Tos,
 }
 
+/// Destination of frames which do not match any filter element
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum NonMatchingFrames {
+    #[default]
+    AcceptInRxFifo0,
+    AcceptInRxFifo1,
+    Reject,
+}
+
+impl From<NonMatchingFrames> for u8 {
+    fn from(value: NonMatchingFrames) -> Self {
+        match value {
+            NonMatchingFrames::AcceptInRxFifo0 => 0,
+            NonMatchingFrames::AcceptInRxFifo1 => 1,
+            NonMatchingFrames::Reject => 2,
+        }
+    }
+}
+
+/// Global filter configuration. Default values match the hardware reset values.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct GlobalFilterConfig {
+    pub non_matching_standard: NonMatchingFrames,
+    pub non_matching_extended: NonMatchingFrames,
+    pub reject_remote_standard: bool,
+    pub reject_remote_extended: bool,
+}
+
+/// Operating mode of the node
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum OperatingMode {
+    #[default]
+    Normal,
+    /// Transmitted frames are received back internally, the Tx pin stays recessive and the Rx pin
+    /// is ignored. Useful for self tests without affecting the bus.
+    InternalLoopback,
+    /// Transmitted frames are received back, and also sent on the bus. Acknowledge errors are ignored.
+    ExternalLoopback,
+    /// Bus monitoring (MON): frames are received but never acknowledged, nothing is transmitted.
+    BusMonitoring,
+    /// Restricted operation (ASM): frames are received and acknowledged, but no data, remote or
+    /// error frames are transmitted.
+    Restricted,
+}
+
+/// Source of the timestamp counter (TSS)
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TimestampSource {
+    /// Timestamps are always 0
+    #[default]
+    Disabled,
+    /// Internal counter, incremented every `prescaler` nominal bit times
+    Internal,
+    /// External timestamp counter, shared by all the nodes of the module
+    External,
+}
+
+impl From<TimestampSource> for u8 {
+    fn from(value: TimestampSource) -> Self {
+        match value {
+            TimestampSource::Disabled => 0,
+            TimestampSource::Internal => 1,
+            TimestampSource::External => 2,
+        }
+    }
+}
+
+/// Timestamp counter configuration (TSCC)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TimestampConfig {
+    pub source: TimestampSource,
+    /// Number of nominal bit times per counter increment, in range [1, 16]
+    pub prescaler: u8,
+}
+
+impl Default for TimestampConfig {
+    fn default() -> Self {
+        Self {
+            source: TimestampSource::Disabled,
+            prescaler: 1,
+        }
+    }
+}
+
+/// When the transceiver delay compensation is used
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum TdcMode {
+    /// Enabled when the frame mode uses bit rate switching and the data bit rate prescaler is 1
+    /// or 2, the only ones the compensation works with
+    #[default]
+    Auto,
+    /// Enabled in all the FD frame modes. The configuration is rejected with
+    /// `ConfigError::InvalidBitTiming` if the data bit rate prescaler is greater than 2.
+    Enabled,
+    Disabled,
+}
+
+/// Transceiver delay compensation (TDC). In the data phase of FD frames with bit rate switching,
+/// the transmitted bits are checked at a secondary sample point, placed at the measured
+/// transceiver delay plus `offset`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct TransceiverDelayCompensation {
+    pub mode: TdcMode,
+    /// Offset (TDCO) in minimum time quanta, in range [0, 127]. When `None`, it is the position
+    /// of the data phase sample point.
+    pub offset: Option<u8>,
+    /// Filter window length (TDCF) in minimum time quanta, in range [0, 127]. Dominant edges
+    /// closer than this to the start of the measurement are ignored. 0 disables the filter.
+    pub filter_window: u8,
+}
+
 #[derive(Default)]
 pub struct NodeConfig {
     pub clock_source: ClockSource,
     pub baud_rate: BitTimingConfig,
     pub fast_baud_rate: FastBitTimingConfig,
-    pub transceiver_delay_offset: u8,
+    pub transceiver_delay_compensation: TransceiverDelayCompensation,
     pub frame_mode: FrameMode,
+    pub global_filter: GlobalFilterConfig,
+    pub bus_off_recovery: BusOffRecovery,
+    pub operating_mode: OperatingMode,
+    pub timestamp: TimestampConfig,
+    /// Message RAM sections of the node, for example one of [`crate::can::MessageRamLayout`].
+    /// The layout is checked when the node is taken, then `setup_tx`, `setup_rx` and the filter
+    /// list setup reject the configurations which do not fit in its sections or do not use its
+    /// start addresses.
+    pub ram_layout: Option<NodeRamLayout>,
 }
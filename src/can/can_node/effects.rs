@@ -5,6 +5,9 @@ use crate::can::{DataFieldSize, TxMode};
 use crate::pac;
 use pac::RegisterValue;
 
+/// Writable bits of IR: bits 20, 21, 29 and 31:30 are reserved
+const INTERRUPT_FLAGS_MASK: u32 = 0x1FCF_FFFF;
+
 pub(super) struct NodeEffects<T> {
     reg: T,
 }
@@ -81,24 +84,14 @@ macro_rules! impl_can_node_effect {
                 let overwrite = mode == RxFifoMode::Overwrite;
                 // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.setup_rx after node.effects.enable_configuration_change has been called in Node::new.
                 // bits 1:0, 23 are written with 0, overwrite is in range [0, 1]
-                unsafe {
-                    self.reg
-                        .rx()
-                        .rxf0ci()
-                        .modify(|r| r.f0om().set(overwrite))
-                };
+                unsafe { self.reg.rx().rxf0ci().modify(|r| r.f0om().set(overwrite)) };
             }
 
             pub(crate) fn set_rx_fifo1_operating_mode(&self, mode: RxFifoMode) {
                 let overwrite = mode == RxFifoMode::Overwrite;
                 // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.setup_rx after node.effects.enable_configuration_change has been called in Node::new.
                 // bits 1:0, 23 are written with 0, overwrite is in range [0, 1]
-                unsafe {
-                    self.reg
-                        .rx()
-                        .rxf1ci()
-                        .modify(|r| r.f1om().set(overwrite))
-                };
+                unsafe { self.reg.rx().rxf1ci().modify(|r| r.f1om().set(overwrite)) };
             }
 
             pub(crate) fn enable_tx_buffer_transmission_interrupt(&self, tx_buffer_id: TxBufferId) {
@@ -170,14 +163,18 @@ macro_rules! impl_can_node_effect {
                 buffer_number: RxBufferId,
             ) -> crate::can::internals::Rx {
                 let num_of_config_bytes = 8u32;
-                let num_of_data_bytes = self.get_data_field_size(buf_from) as u32;
+                let data_field_size = self.get_data_field_size(buf_from);
+                let num_of_data_bytes = u32::from(data_field_size);
                 let tx_buffer_size = num_of_config_bytes + num_of_data_bytes;
                 let tx_buffer_index = tx_buffer_size * u32::from(buffer_number);
 
                 let tx_buffer_element_address =
                     ram_base_address + tx_buffers_start_address as u32 + tx_buffer_index;
 
-                crate::can::internals::Rx::new(tx_buffer_element_address as *mut u8)
+                crate::can::internals::Rx::new(
+                    tx_buffer_element_address as *mut u8,
+                    data_field_size,
+                )
             }
 
             pub(crate) fn clear_rx_buffer_new_data_flag(&self, rx_buffer_id: RxBufferId) {
@@ -285,6 +282,76 @@ macro_rules! impl_can_node_effect {
                 } {}
             }
 
+            pub(crate) fn is_initialization_enabled(&self) -> bool {
+                // SAFETY: INIT bit is RWH
+                unsafe { self.reg.cccri().read() }.init().get()
+            }
+
+            /// Leave the initialization state, set by hardware when the node goes bus off
+            pub(crate) fn clear_initialization(&self) {
+                let cccr = self.reg.cccri();
+
+                // SAFETY: INIT bit is RWH
+                unsafe { cccr.modify(|r| r.init().set(false)) };
+                while {
+                    // SAFETY: INIT bit is RWH
+                    unsafe { cccr.read() }.init().get()
+                } {}
+            }
+
+            /// Stop the bus activity, pending transfers are completed first
+            pub(crate) fn set_initialization(&self) {
+                let cccr = self.reg.cccri();
+
+                // SAFETY: INIT bit is RWH
+                unsafe { cccr.modify(|r| r.init().set(true)) };
+                while {
+                    // SAFETY: INIT bit is RWH
+                    !unsafe { cccr.read() }.init().get()
+                } {}
+            }
+
+            /// Request the clock stop and wait for the acknowledge. The node completes the pending
+            /// transfers and enters the initialization state before acknowledging.
+            pub(crate) fn request_clock_stop(&self) {
+                let cccr = self.reg.cccri();
+
+                // SAFETY: CSR bit is RW
+                unsafe { cccr.modify(|r| r.csr().set(true)) };
+                while {
+                    // SAFETY: CSA bit is RH
+                    !unsafe { cccr.read() }.csa().get()
+                } {}
+            }
+
+            /// Withdraw the clock stop request and wait until the node leaves the power down mode.
+            /// The node stays in the initialization state.
+            pub(crate) fn clear_clock_stop_request(&self) {
+                let cccr = self.reg.cccri();
+
+                // SAFETY: CSR bit is RW
+                unsafe { cccr.modify(|r| r.csr().set(false)) };
+                while {
+                    // SAFETY: CSA bit is RH
+                    unsafe { cccr.read() }.csa().get()
+                } {}
+            }
+
+            pub(crate) fn get_error_counter_register(&self) -> u32 {
+                // SAFETY: ECR is read only, reading it clears CEL
+                unsafe { self.reg.ecri().read() }.get_raw()
+            }
+
+            pub(crate) fn get_protocol_status_register(&self) -> u32 {
+                // SAFETY: PSR is read only, reading it sets LEC and DLEC to 7
+                unsafe { self.reg.psri().read() }.get_raw()
+            }
+
+            pub(crate) fn get_interrupt_flags(&self) -> u32 {
+                // SAFETY: each bit of IR is RWH
+                unsafe { self.reg.iri().read() }.get_raw()
+            }
+
             pub(crate) fn set_nominal_bit_timing(&self, timing: &NominalBitTiming) {
                 // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.configure_baud_rate after node.effects.enable_configuration_change has been called in Node::new.
                 // bit 7 is written with 0, TODO timing.brp should be in range [0, 2^9)
@@ -353,13 +420,28 @@ macro_rules! impl_can_node_effect {
                 };
             }
 
-            pub(crate) fn set_transceiver_delay_compensation_offset(&self, delay: u8) {
-                // SAFETY: write is CCE and INIT protected: called after node.effects.enable_configuration_change has been called in Node::new.
+            pub(crate) fn set_transceiver_delay_compensation(
+                &self,
+                enabled: bool,
+                offset: u8,
+                filter_window: u8,
+            ) {
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.configure_transceiver_delay_compensation after node.effects.enable_configuration_change has been called in Node::new.
+                // bits 7 and 31:15 are written with 0, offset and filter_window are masked to 7 bits
+                unsafe {
+                    self.reg
+                        .tdcri()
+                        .modify(|r| r.tdco().set(offset & 0x7F).tdcf().set(filter_window & 0x7F))
+                };
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.configure_transceiver_delay_compensation after node.effects.enable_configuration_change has been called in Node::new.
+                // bits 15:13, 22:21 and 31:24 are written with 0, TDC bit is RW
+                unsafe { self.reg.dbtpi().modify(|r| r.tdc().set(enabled)) };
+            }
+
+            pub(crate) fn disable_transceiver_delay_compensation(&self) {
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.set_bit_timing, the configuration change is enabled in Configurable state.
                 // bits 15:13, 22:21 and 31:24 are written with 0, TDC bit is RW
-                unsafe { self.reg.dbtpi().modify(|r| r.tdc().set(true)) };
-                // SAFETY: write is CCE and INIT protected: called after node.effects.enable_configuration_change has been called in Node::new.
-                // bits 7 and 31:15 are written with 0, TODO delay should be in range [0, 2^7)
-                unsafe { self.reg.tdcri().modify(|r| r.tdco().set(delay)) };
+                unsafe { self.reg.dbtpi().modify(|r| r.tdc().set(false)) };
             }
 
             pub(crate) fn enable_interrupt(&self, interrupt: Interrupt) {
@@ -384,6 +466,17 @@ macro_rules! impl_can_node_effect {
                 };
             }
 
+            /// Clear all the interrupt flags set in `flags`, with a single write
+            #[inline]
+            pub(crate) fn clear_interrupt_flags(&self, flags: u32) {
+                // SAFETY: bits 20, 21, 29 and 31:30 are written with 0, writing 0 to the other bits has no effect
+                unsafe {
+                    self.reg
+                        .iri()
+                        .init(|r| r.set_raw(flags & INTERRUPT_FLAGS_MASK))
+                };
+            }
+
             pub(crate) fn set_interrupt_routing_group_1(&self, line: u32, group: u32) {
                 // SAFETY: TODO: line should be in range [0, 16) and group should be in range [0, 8)
                 unsafe {
@@ -399,7 +492,6 @@ macro_rules! impl_can_node_effect {
                 // SAFETY: TODO: line should be in range [0, 16) and group should be in range [0, 8)
                 unsafe {
                     self.reg.grint2i().modify(|r| {
-
                         let mut v = r.get_raw();
                         v |= line << group;
                         r.set_raw(v)
@@ -412,6 +504,38 @@ macro_rules! impl_can_node_effect {
                 unsafe { self.reg.npcri().modify(|r| r.lbm().set(true)) };
             }
 
+            pub(crate) fn set_timestamp_counter_configuration(&self, tss: u8, tcp: u8) {
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.configure_timestamp after node.effects.enable_configuration_change has been called in Node::new.
+                // bits 15:2 and 31:20 are written with 0, tss is in range [0, 2], tcp is in range [0, 15]
+                unsafe { self.reg.tscci().modify(|r| r.tss().set(tss).tcp().set(tcp)) };
+            }
+
+            pub(crate) fn get_timestamp_counter(&self) -> u16 {
+                // SAFETY: TSC is RH
+                unsafe { self.reg.tscvi().read() }.tsc().get()
+            }
+
+            pub(crate) fn reset_timestamp_counter(&self) {
+                // SAFETY: any write access to TSCV resets the counter
+                unsafe { self.reg.tscvi().init(|r| r) };
+            }
+
+            pub(crate) fn set_operating_mode(&self, test: bool, mon: bool, asm: bool) {
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.configure_operating_mode after node.effects.enable_configuration_change has been called in Node::new.
+                // bits 11:10 and 31:16 are written with 0, test, mon and asm are in range [0, 1]
+                unsafe {
+                    self.reg
+                        .cccri()
+                        .modify(|r| r.test().set(test).mon().set(mon).asm().set(asm))
+                };
+            }
+
+            pub(crate) fn set_loopback_mode(&self, enable: bool) {
+                // SAFETY: TEST is writable because CCCR.TEST has been set in Node<Configurable>.configure_operating_mode.
+                // bits 3:0 and 31:8 are written with 0, LBCK is RW
+                unsafe { self.reg.testi().modify(|r| r.lbck().set(enable)) };
+            }
+
             pub(crate) fn connect_pin_rx(&self, rx_sel: RxSel) {
                 // SAFETY: bits 7:3 and 31:11 are written with 0, rx_sel is guaranteed to take only allowed values
                 unsafe { self.reg.npcri().modify(|r| r.rxsel().set(rx_sel.into())) };
@@ -427,6 +551,26 @@ macro_rules! impl_can_node_effect {
                 unsafe { self.reg.rx().rxf1si().read() }.f1fl().get()
             }
 
+            pub(crate) fn get_rx_fifo0_status(&self) -> u32 {
+                // SAFETY: RXF0S is read only
+                unsafe { self.reg.rx().rxf0si().read() }.get_raw()
+            }
+
+            pub(crate) fn get_rx_fifo1_status(&self) -> u32 {
+                // SAFETY: RXF1S is read only
+                unsafe { self.reg.rx().rxf1si().read() }.get_raw()
+            }
+
+            pub(crate) fn is_rx_fifo0_message_lost(&self) -> bool {
+                // SAFETY: RF0L is RH
+                unsafe { self.reg.rx().rxf0si().read() }.rf0l().get()
+            }
+
+            pub(crate) fn is_rx_fifo1_message_lost(&self) -> bool {
+                // SAFETY: RF1L is RH
+                unsafe { self.reg.rx().rxf1si().read() }.rf1l().get()
+            }
+
             pub(crate) fn set_rx_fifo1_size(&self, size: u8) {
                 // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.setup_rx after node.effects.enable_configuration_change has been called in Node::new.
                 // bits 1:0, 23 are written with 0, TODO size should be in range [0, 2^7)
@@ -460,6 +604,21 @@ macro_rules! impl_can_node_effect {
                 unsafe { self.reg.tx().txefsi().read() }.tefl().get() == true
             }
 
+            pub(crate) fn get_tx_event_fifo_fill_level(&self) -> u8 {
+                // SAFETY: EFFL is RH
+                unsafe { self.reg.tx().txefsi().read() }.effl().get()
+            }
+
+            pub(crate) fn get_tx_event_fifo_get_index(&self) -> u8 {
+                // SAFETY: EFGI is RH
+                unsafe { self.reg.tx().txefsi().read() }.efgi().get()
+            }
+
+            pub(crate) fn set_tx_event_fifo_acknowledge_index(&self, index: u8) {
+                // SAFETY: bits 31:5 are written with 0, index is read from EFGI, so it is in range [0, 2^5)
+                unsafe { self.reg.tx().txefai().modify(|r| r.efai().set(index)) };
+            }
+
             pub(crate) fn is_tx_event_fifo_full(&self) -> bool {
                 // SAFETY: EFF is RH
                 unsafe { self.reg.tx().txefsi().read() }.eff().get() == true
@@ -473,55 +632,82 @@ macro_rules! impl_can_node_effect {
             pub(crate) fn pause_trasmission(&self, enable: bool) {
                 // SAFETY: write is CCE and INIT protected: TODO: never used
                 // bits 11:10 and 31:16 are written with 0, enable is in range [0, 1]
-                unsafe {
-                    self.reg
-                        .cccri()
-                        .modify(|r| r.txp().set(enable))
-                };
+                unsafe { self.reg.cccri().modify(|r| r.txp().set(enable)) };
             }
 
             pub(crate) fn set_standard_filter_list_start_address(&self, address: u16) {
-                // SAFETY: write is CCE and INIT protected: TODO: never used
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.setup_standard_filter_list after node.effects.enable_configuration_change has been called in Node::new.
                 // bits 1:0 and 31:24 are written with 0, TODO: address should be in range [0, 2^14)
                 unsafe { self.reg.sidfci().modify(|r| r.flssa().set(address >> 2)) };
             }
 
             pub(crate) fn set_standard_filter_list_size(&self, size: u8) {
-                // SAFETY: write is CCE and INIT protected: TODO: never used
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.setup_standard_filter_list after node.effects.enable_configuration_change has been called in Node::new.
                 // bits 1:0 and 31:24 are written with 0, size is in range [0, 2^8)
                 unsafe { self.reg.sidfci().modify(|r| r.lss().set(size.into())) };
             }
 
-            pub(crate) fn reject_remote_frames_with_standard_id(&self) {
-                 // SAFETY: write is CCE and INIT protected: TODO: never used
-                // bits 31:6 are written with 0, RRFS is a RW bit
+            pub(crate) fn get_standard_filter_list_start_address(&self) -> u16 {
+                // SAFETY: FLSSA is RW
+                let flssa = unsafe { self.reg.sidfci().read() }.flssa().get();
+                u16::from(flssa) << 2
+            }
+
+            pub(crate) fn get_standard_filter_list_size(&self) -> u16 {
+                // SAFETY: LSS is RW
+                u16::from(unsafe { self.reg.sidfci().read() }.lss().get())
+            }
+
+            pub(crate) fn set_global_filter_configuration(
+                &self,
+                accept_non_matching_standard: u8,
+                accept_non_matching_extended: u8,
+                reject_remote_standard: bool,
+                reject_remote_extended: bool,
+            ) {
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>::new after node.effects.enable_configuration_change.
+                // bits 31:6 are written with 0, ANFS and ANFE are in range [0, 3], RRFS and RRFE are RW bits
                 unsafe {
-                    self.reg
-                        .gfci()
-                        .modify(|r| r.rrfs().set(true))
+                    self.reg.gfci().modify(|r| {
+                        r.anfs()
+                            .set(accept_non_matching_standard)
+                            .anfe()
+                            .set(accept_non_matching_extended)
+                            .rrfs()
+                            .set(reject_remote_standard)
+                            .rrfe()
+                            .set(reject_remote_extended)
+                    })
                 };
             }
 
             pub(crate) fn set_extended_filter_list_start_address(&self, address: u16) {
-                // SAFETY: write is CCE and INIT protected: TODO: never used
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.setup_extended_filter_list after node.effects.enable_configuration_change has been called in Node::new.
                 // bits 1:0 and 31:24 are written with 0, TODO: address should be in range [0, 2^14)
                 unsafe { self.reg.xidfci().modify(|r| r.flesa().set(address >> 2)) };
             }
 
             pub(crate) fn set_extended_filter_list_size(&self, size: u8) {
-                // SAFETY: write is CCE and INIT protected: TODO: never used
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.setup_extended_filter_list after node.effects.enable_configuration_change has been called in Node::new.
                 // bits 1:0 and 31:24 are written with 0, size is in range [0, 2^8)
-                unsafe {
-                    self.reg
-                        .xidfci()
-                        .modify(|r| r.lse().set(size.into()))
-                };
+                unsafe { self.reg.xidfci().modify(|r| r.lse().set(size.into())) };
+            }
+
+            pub(crate) fn get_extended_filter_list_start_address(&self) -> u16 {
+                // SAFETY: FLESA is RW
+                let flesa = unsafe { self.reg.xidfci().read() }.flesa().get();
+                u16::from(flesa) << 2
             }
 
-            pub(crate) fn reject_remote_frames_with_extended_id(&self) {
-                 // SAFETY: write is CCE and INIT protected: TODO: never used
-                // bits 31:6 are written with 0, RRFE is a RW bit
-                unsafe { self.reg.gfci().modify(|r| r.rrfe().set(true)) };
+            pub(crate) fn get_extended_filter_list_size(&self) -> u16 {
+                // SAFETY: LSE is RW
+                u16::from(unsafe { self.reg.xidfci().read() }.lse().get())
+            }
+
+            pub(crate) fn set_extended_id_and_mask(&self, mask: u32) {
+                // SAFETY: write is CCE and INIT protected: called in Node<Configurable>.set_extended_id_mask after node.effects.enable_configuration_change has been called in Node::new.
+                // bits 31:29 are written with 0, mask is in range [0, 2^29)
+                unsafe { self.reg.xidami().modify(|r| r.eidm().set(mask)) };
             }
 
             pub(crate) fn get_tx_fifo_queue_put_index(&self) -> u8 {
@@ -543,6 +729,15 @@ macro_rules! impl_can_node_effect {
                 unsafe { RxBufferId::new_unchecked(idx) }
             }
 
+            /// New data flags of the dedicated Rx buffers, bit n is set when buffer n has new data
+            pub(crate) fn get_rx_buffers_new_data(&self) -> u64 {
+                // SAFETY: each bit of NDAT1i is RWH
+                let ndat1 = unsafe { self.reg.ndat1i().read() }.get_raw();
+                // SAFETY: each bit of NDAT2i is RWH
+                let ndat2 = unsafe { self.reg.ndat2i().read() }.get_raw();
+                (u64::from(ndat2) << 32) | u64::from(ndat1)
+            }
+
             pub(crate) fn is_rx_buffer_new_data_updated(&self, rx_buffer_id: u8) -> bool {
                 let (data, mask) = if rx_buffer_id < 32 {
                     // last number value in the reg name is the node id
@@ -590,6 +785,26 @@ macro_rules! impl_can_node_effect {
                 (data & mask) != 0
             }
 
+            #[inline]
+            pub(crate) fn is_tx_buffer_cancellation_finished(&self, tx_buffer_id: u8) -> bool {
+                // SAFETY: each bit of TXBCFI is RH
+                let data = unsafe { self.reg.tx().txbcfi().read() }.get_raw();
+                let mask = 1u32 << u32::from(tx_buffer_id);
+                (data & mask) != 0
+            }
+
+            #[inline]
+            pub(crate) fn set_tx_buffer_cancellation_request(&self, mask: u32) {
+                // SAFETY: each bit of TXBCRI is RWH, writing 0 has no effect
+                unsafe { self.reg.tx().txbcri().init(|r| r.set_raw(mask)) };
+            }
+
+            #[inline]
+            pub(crate) fn get_tx_buffer_request_pending(&self) -> u32 {
+                // SAFETY: each bit of TXBRPI is RH
+                unsafe { self.reg.tx().txbrpi().read() }.get_raw()
+            }
+
             #[inline]
             pub(crate) fn set_tx_buffer_add_request(&self, id: u8) {
                 let txbari = self.reg.tx().txbari();
@@ -682,9 +897,8 @@ macro_rules! impl_can_node_effect {
             }
 
             pub(crate) fn get_tx_buffer_data_field_size(&self) -> u8 {
-                let size_code: u8 =
                 // SAFETY: each bit of TXESCI is at least R
-                    (unsafe { self.reg.tx().txesci().read() }.get_raw() & 0x2) as u8;
+                let size_code: u8 = unsafe { self.reg.tx().txesci().read() }.tbds().get();
                 if size_code < (DataFieldSize::_32 as u8) {
                     (size_code + 2) * 4
                 } else {
@@ -0,0 +1,55 @@
+use super::{CanError, Configured, Node, NodeId, ReceiveError, TransmitError};
+use crate::can::frame::OwnedFrame;
+
+impl embedded_can::Error for CanError {
+    fn kind(&self) -> embedded_can::ErrorKind {
+        match self {
+            CanError::Receive(ReceiveError::MessageLost) => embedded_can::ErrorKind::Overrun,
+            _ => embedded_can::ErrorKind::Other,
+        }
+    }
+}
+
+macro_rules! impl_embedded_can {
+    ($ModuleReg:ty, $NodeReg:path) => {
+        impl<I: NodeId> embedded_can::nb::Can for Node<$NodeReg, $ModuleReg, I, Configured> {
+            type Frame = OwnedFrame;
+            type Error = CanError;
+
+            fn transmit(
+                &mut self,
+                frame: &Self::Frame,
+            ) -> nb::Result<Option<Self::Frame>, Self::Error> {
+                match self.transmit_frame(frame) {
+                    Ok(()) => Ok(None),
+                    Err(TransmitError::Busy) => Err(nb::Error::WouldBlock),
+                    Err(e) => Err(nb::Error::Other(CanError::Transmit(e))),
+                }
+            }
+
+            fn receive(&mut self) -> nb::Result<Self::Frame, Self::Error> {
+                match self.receive_owned_frame() {
+                    Ok(frame) => Ok(frame),
+                    Err(ReceiveError::FifoEmpty) => Err(nb::Error::WouldBlock),
+                    Err(e) => Err(nb::Error::Other(CanError::Receive(e))),
+                }
+            }
+        }
+
+        impl<I: NodeId> embedded_can::blocking::Can for Node<$NodeReg, $ModuleReg, I, Configured> {
+            type Frame = OwnedFrame;
+            type Error = CanError;
+
+            fn transmit(&mut self, frame: &Self::Frame) -> Result<(), Self::Error> {
+                nb::block!(embedded_can::nb::Can::transmit(self, frame)).map(|_| ())
+            }
+
+            fn receive(&mut self) -> Result<Self::Frame, Self::Error> {
+                nb::block!(embedded_can::nb::Can::receive(self))
+            }
+        }
+    };
+}
+
+impl_embedded_can!(crate::pac::can0::Can0, crate::pac::can0::N);
+impl_embedded_can!(crate::pac::can1::Can1, crate::pac::can1::N);
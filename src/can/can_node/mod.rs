@@ -3,11 +3,20 @@
 
 pub mod config;
 mod effects;
+mod hal;
+mod rx_queue;
+mod status;
+mod timestamp;
+mod waker;
 
 use super::baud_rate::*;
-use super::frame::{DataLenghtCode, Frame};
-use super::internals::Tx;
-use super::msg::TxBufferId;
+use super::frame::{DataLenghtCode, Frame, OwnedFrame};
+use super::internals::{ExtFilter, Rx, StdFilter, Tx, TxEventElement};
+use super::msg::{RxBufferId, RxBuffersNewData, TxBufferId, TxEvent, TxMessage};
+use super::ram_layout::{
+    NodeRamLayout, EXTENDED_FILTER_ELEMENT_SIZE, MAX_EXTENDED_FILTERS, MAX_STANDARD_FILTERS,
+    STANDARD_FILTER_ELEMENT_SIZE, TX_EVENT_ELEMENT_SIZE,
+};
 use super::{can_module, Module, ModuleId};
 use crate::can::can_module::ClockSelect;
 use crate::can::can_node::effects::NodeEffects;
@@ -16,13 +25,27 @@ use crate::can::msg::FrameMode;
 use crate::can::msg::MessageId;
 use crate::can::msg::ReadFrom;
 use crate::can::msg::RxMessage;
+use crate::can::msg::{Filter, FilterElementConfiguration};
 use crate::cpu::Priority;
+use crate::gpio::alt::{CanPins, CanRxPin};
+use crate::gpio::{PinPull, PinSpeed, Pull, Speed};
 use crate::log::info;
 use crate::pac::common::RegisterValue;
-use crate::scu::wdt_call;
-pub use config::NodeConfig;
+pub use config::{
+    GlobalFilterConfig, NodeConfig, NonMatchingFrames, OperatingMode, TdcMode, TimestampConfig,
+    TimestampSource, TransceiverDelayCompensation,
+};
+use core::future::poll_fn;
 use core::marker::PhantomData;
-use core::mem::transmute;
+use core::task::Poll;
+pub use rx_queue::RxQueue;
+pub use status::{
+    Activity, BusOffRecovery, DrainReport, ErrorCounters, ErrorState, ErrorStateChange,
+    LastErrorCode, ProtocolStatus, RxFifoStatus,
+};
+pub use timestamp::TimestampExtender;
+pub use waker::wake_node;
+use waker::{waker_index, RX_WAKERS, TX_WAKERS};
 
 #[derive(PartialEq, Debug, Default)]
 pub enum FrameType {
@@ -55,8 +78,13 @@ pub enum RxMode {
     SharedAll,
 }
 
-// TODO Suspicious
-const TX_BUFFER_START_ADDRESS: u32 = 0x0440u32;
+const MAX_STANDARD_ID: u32 = 0x7FF;
+const MAX_EXTENDED_ID: u32 = 0x1FFF_FFFF;
+
+/// New message flags (RF0N, RF1N, DRX) cleared by `handle_rx_interrupt`
+const RX_NEW_MESSAGE_FLAGS: u32 = (1 << Interrupt::RxFifo0newMessage as u32)
+    | (1 << Interrupt::RxFifo1newMessage as u32)
+    | (1 << Interrupt::MessageStoredToDedicatedRxBuffer as u32);
 
 pub trait NodeId {
     const INDEX: usize;
@@ -87,28 +115,120 @@ impl NodeId for Node3 {
 // Type state of Node
 pub struct Configured;
 pub struct Configurable;
+/// The node clock is stopped (power down mode)
+pub struct ClockStopped;
 
 pub struct Node<N, M, I: NodeId, State> {
     effects: NodeEffects<N>,
     frame_mode: FrameMode,
+    bus_off_recovery: BusOffRecovery,
     ram_base_address: u32,
+    ram_layout: Option<NodeRamLayout>,
     _phantom: PhantomData<(M, I, State)>,
 
     rx_config: Option<RxConfig>,
+    tx_config: Option<TxConfig>,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum ConfigError {
     CannotSetClockSource,
+    /// The bit timing is out of the register ranges, or cannot be calculated, or the transceiver
+    /// delay compensation is enabled with a data bit rate prescaler greater than 2
+    InvalidBitTiming,
+    /// The message RAM sections overlap, are not aligned, exceed the message RAM or are too
+    /// small for the requirements of the layout
+    InvalidRamLayout,
+    /// The Tx or Rx configuration needs more elements or larger data fields than the
+    /// [`NodeConfig::ram_layout`] reserves, or uses other start addresses
+    RamLayoutMismatch,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
 pub enum TransmitError {
+    /// The Tx FIFO/queue is full, or the dedicated Tx buffer has a pending request
     Busy,
+    /// The data length has no DLC, or exceeds the data field size of the Tx buffers
     InvalidDataLength,
     InvalidAccess,
+    /// Tx buffers have not been configured
+    NotConfigured,
+    /// The buffer is not a dedicated Tx buffer, or the Tx FIFO/queue is not enabled
+    InvalidBuffer,
+    /// The frame mode (FD, bit rate switch) is not enabled on the node
+    InvalidFrameMode,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TxEventError {
+    /// The Tx event FIFO has not been configured
+    NotConfigured,
+    /// The Tx event FIFO does not contain any event
+    FifoEmpty,
+    /// An event has been discarded because the Tx event FIFO was full (TEFL).
+    /// The flag is cleared when reported, events still in the FIFO can be read afterwards.
+    EventLost,
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ReceiveError {
+    /// Rx FIFOs and buffers have not been configured
+    NotConfigured,
+    /// The Rx FIFO does not contain any message
+    FifoEmpty,
+    /// The dedicated Rx buffer has not received a new message
+    NoNewData,
+    /// A message has been discarded because the Rx FIFO was full (RF0L/RF1L).
+    /// The flag is cleared when reported, messages still in the FIFO can be read afterwards.
+    /// Not returned in overwrite mode, where the dropped messages are reported by
+    /// [`crate::can::Node::drain_fifo`] and [`crate::can::Node::handle_rx_interrupt`].
+    MessageLost,
+    /// The destination buffer is shorter than the received frame
+    BufferTooSmall { required: usize },
+}
+
+/// Error of the `embedded_can` traits implementation
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum CanError {
+    Transmit(TransmitError),
+    Receive(ReceiveError),
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum FilterError {
+    InvalidListSize,
+    InvalidFilterNumber,
+    InvalidId,
+    /// The start address differs from the one of the [`NodeConfig::ram_layout`]
+    InvalidStartAddress,
+    /// The dedicated Rx buffer is not reserved by the [`NodeConfig::ram_layout`]
+    InvalidRxBuffer,
 }
 
 macro_rules! impl_can_node {
     ($ModuleReg:ty, $NodeReg:path, $ModuleId: ty) => {
+        // Methods valid in any state
+        impl<I: NodeId, State> Node<$NodeReg, $ModuleReg, I, State> {
+            fn into_state<S>(self) -> Node<$NodeReg, $ModuleReg, I, S> {
+                Node {
+                    effects: self.effects,
+                    _phantom: PhantomData,
+                    frame_mode: self.frame_mode,
+                    bus_off_recovery: self.bus_off_recovery,
+                    ram_base_address: self.ram_base_address,
+                    ram_layout: self.ram_layout,
+                    rx_config: self.rx_config,
+                    tx_config: self.tx_config,
+                }
+            }
+
+            /// Stop the node before it is given back to its module
+            pub(super) fn release(self) {
+                self.effects.clear_clock_stop_request();
+                self.effects.set_initialization();
+            }
+        }
+
         // Methods only valid on a configurable node
         impl<I: NodeId> Node<$NodeReg, $ModuleReg, I, Configurable> {
             /// Only a module can create a self. This function is only accessible from within this crate.
@@ -117,6 +237,12 @@ macro_rules! impl_can_node {
                 node_id: I,
                 config: NodeConfig,
             ) -> Result<Node<$NodeReg, $ModuleReg, I, Configurable>, ConfigError> {
+                if let Some(ram_layout) = &config.ram_layout {
+                    if !ram_layout.is_valid() {
+                        return Err(ConfigError::InvalidRamLayout);
+                    }
+                }
+
                 let node_index = node_id.as_index();
                 #[allow(clippy::indexing_slicing)]
                 let node_reg = module.registers().n()[node_index];
@@ -131,23 +257,35 @@ macro_rules! impl_can_node {
                     effects,
                     _phantom: PhantomData,
                     frame_mode: config.frame_mode,
+                    bus_off_recovery: config.bus_off_recovery,
                     ram_base_address: module.ram_base_address(),
+                    ram_layout: config.ram_layout,
                     rx_config: None,
+                    tx_config: None,
                 };
 
                 node.effects.enable_configuration_change();
 
-                node.configure_baud_rate(&config.baud_rate);
+                node.configure_baud_rate(&config.baud_rate)?;
 
-                // for CAN FD frames, set fast baud rate
+                // for CAN FD frames, set fast baud rate and transceiver delay compensation
                 if config.frame_mode != FrameMode::Standard {
-                    node.configure_fast_baud_rate(&config.fast_baud_rate);
+                    let data_bit_timing = node.configure_fast_baud_rate(&config.fast_baud_rate)?;
+                    node.configure_transceiver_delay_compensation(
+                        config.frame_mode,
+                        data_bit_timing,
+                        &config.transceiver_delay_compensation,
+                    )?;
                 }
 
-                // TODO Check if transceiver_delay_offset is needed only for CAN FD
-                if config.transceiver_delay_offset != 0 {
-                    node.effects
-                        .set_transceiver_delay_compensation_offset(config.transceiver_delay_offset);
+                node.configure_global_filter(&config.global_filter);
+
+                if config.operating_mode != OperatingMode::Normal {
+                    node.configure_operating_mode(config.operating_mode);
+                }
+
+                if config.timestamp.source != TimestampSource::Disabled {
+                    node.configure_timestamp(&config.timestamp);
                 }
 
                 Ok(node)
@@ -156,17 +294,51 @@ macro_rules! impl_can_node {
             #[must_use]
             pub fn lock_configuration(self) -> Node<$NodeReg, $ModuleReg, I, Configured> {
                 self.effects.disable_configuration_change();
+                self.into_state()
+            }
 
-                Node {
-                    effects: self.effects,
-                    _phantom: PhantomData,
-                    frame_mode: self.frame_mode,
-                    ram_base_address: self.ram_base_address,
-                    rx_config: self.rx_config,
+            /// Change the frame mode and the bit timings, for example after
+            /// [`Node::unlock_configuration`]. The fast bit timing is only used in FD frame modes.
+            pub fn set_bit_timing(
+                &mut self,
+                frame_mode: FrameMode,
+                baud_rate: &BitTimingConfig,
+                fast_baud_rate: &FastBitTimingConfig,
+                transceiver_delay_compensation: &TransceiverDelayCompensation,
+            ) -> Result<(), ConfigError> {
+                self.configure_baud_rate(baud_rate)?;
+
+                if frame_mode == FrameMode::Standard {
+                    // The compensation of the previous FD configuration must not stay enabled
+                    self.effects.disable_transceiver_delay_compensation();
+                } else {
+                    let data_bit_timing = self.configure_fast_baud_rate(fast_baud_rate)?;
+                    self.configure_transceiver_delay_compensation(
+                        frame_mode,
+                        data_bit_timing,
+                        transceiver_delay_compensation,
+                    )?;
                 }
+
+                self.frame_mode = frame_mode;
+                self.set_frame_mode(frame_mode);
+
+                Ok(())
             }
 
-            pub fn setup_tx(&self, tx_config: &TxConfig) {
+            /// Configure the Tx buffers. If the node has a [`NodeConfig::ram_layout`], the Tx
+            /// event FIFO and the Tx buffers must fit in its sections and start at their
+            /// addresses, otherwise nothing is configured.
+            pub fn setup_tx(&mut self, tx_config: &TxConfig) -> Result<(), ConfigError> {
+                if let Some(layout) = &self.ram_layout {
+                    if !tx_config.fits_in(layout) {
+                        return Err(ConfigError::RamLayoutMismatch);
+                    }
+                }
+
+                let tx_config = *tx_config;
+                self.tx_config = Some(tx_config);
+
                 self.set_tx_buffer_data_field_size(tx_config.buffer_data_field_size);
                 self.effects
                     .set_tx_buffer_start_address(tx_config.tx_buffers_start_address);
@@ -219,9 +391,20 @@ macro_rules! impl_can_node {
                 }
 
                 self.set_frame_mode(self.frame_mode);
+
+                Ok(())
             }
 
-            pub fn setup_rx(&mut self, rx_config: RxConfig) {
+            /// Configure the Rx FIFOs and buffers. If the node has a [`NodeConfig::ram_layout`],
+            /// the Rx FIFOs and the Rx buffers must fit in its sections and start at their
+            /// addresses, otherwise nothing is configured.
+            pub fn setup_rx(&mut self, rx_config: RxConfig) -> Result<(), ConfigError> {
+                if let Some(layout) = &self.ram_layout {
+                    if !rx_config.fits_in(layout) {
+                        return Err(ConfigError::RamLayoutMismatch);
+                    }
+                }
+
                 self.rx_config = Some(rx_config);
 
                 let mode = rx_config.mode;
@@ -275,27 +458,173 @@ macro_rules! impl_can_node {
                 }
 
                 self.set_frame_mode(self.frame_mode);
+
+                Ok(())
             }
 
-            // TODO I think this should accept pins as provided by gpio module
-            pub fn setup_pins(&self, pins: Option<&Pins<$ModuleId, I>>) {
-                match pins {
-                    Some(pins) => {
-                        self.connect_pin_rx(
-                            &pins.rx,
-                            InputMode::PULL_UP,
-                            PadDriver::CmosAutomotiveSpeed3,
-                        );
-                        self.connect_pin_tx(
-                            &pins.tx,
-                            OutputMode::PUSH_PULL,
-                            PadDriver::CmosAutomotiveSpeed3,
-                        );
+            /// Set start address (in message RAM) and size of the standard ID filter list.
+            /// All the elements of the list are disabled. If the node has a
+            /// [`NodeConfig::ram_layout`], the list must fit in its section and start at its
+            /// address.
+            pub fn setup_standard_filter_list(
+                &self,
+                start_address: u16,
+                size: u8,
+            ) -> Result<(), FilterError> {
+                if size > MAX_STANDARD_FILTERS {
+                    return Err(FilterError::InvalidListSize);
+                }
+
+                if let Some(layout) = &self.ram_layout {
+                    if size > layout.requirements.standard_filters {
+                        return Err(FilterError::InvalidListSize);
                     }
-                    None => {
-                        self.effects.enable_loopback();
+                    if size > 0 && start_address != layout.standard_filter_list_start_address {
+                        return Err(FilterError::InvalidStartAddress);
                     }
-                };
+                }
+
+                self.effects
+                    .set_standard_filter_list_start_address(start_address);
+                self.effects.set_standard_filter_list_size(size);
+
+                for number in 0..size {
+                    self.get_standard_filter_element(number).disable();
+                }
+
+                Ok(())
+            }
+
+            /// Write a standard ID filter element. The list must be set up with
+            /// [`Self::setup_standard_filter_list`] first.
+            pub fn set_standard_filter(&self, filter: &Filter) -> Result<(), FilterError> {
+                if u16::from(filter.number) >= self.effects.get_standard_filter_list_size() {
+                    return Err(FilterError::InvalidFilterNumber);
+                }
+
+                let id2_is_id_or_mask =
+                    filter.element_configuration != FilterElementConfiguration::StoreInRxBuffer;
+
+                if filter.id1 > MAX_STANDARD_ID
+                    || (id2_is_id_or_mask && filter.id2 > MAX_STANDARD_ID)
+                {
+                    return Err(FilterError::InvalidId);
+                }
+
+                self.check_filter_rx_buffer(filter)?;
+
+                self.get_standard_filter_element(filter.number).set(filter);
+
+                Ok(())
+            }
+
+            /// Set start address (in message RAM) and size of the extended ID filter list.
+            /// All the elements of the list are disabled. If the node has a
+            /// [`NodeConfig::ram_layout`], the list must fit in its section and start at its
+            /// address.
+            pub fn setup_extended_filter_list(
+                &self,
+                start_address: u16,
+                size: u8,
+            ) -> Result<(), FilterError> {
+                if size > MAX_EXTENDED_FILTERS {
+                    return Err(FilterError::InvalidListSize);
+                }
+
+                if let Some(layout) = &self.ram_layout {
+                    if size > layout.requirements.extended_filters {
+                        return Err(FilterError::InvalidListSize);
+                    }
+                    if size > 0 && start_address != layout.extended_filter_list_start_address {
+                        return Err(FilterError::InvalidStartAddress);
+                    }
+                }
+
+                self.effects
+                    .set_extended_filter_list_start_address(start_address);
+                self.effects.set_extended_filter_list_size(size);
+
+                for number in 0..size {
+                    self.get_extended_filter_element(number).disable();
+                }
+
+                Ok(())
+            }
+
+            /// Write an extended ID filter element. The list must be set up with
+            /// [`Self::setup_extended_filter_list`] first.
+            pub fn set_extended_filter(&self, filter: &Filter) -> Result<(), FilterError> {
+                if u16::from(filter.number) >= self.effects.get_extended_filter_list_size() {
+                    return Err(FilterError::InvalidFilterNumber);
+                }
+
+                let id2_is_id_or_mask =
+                    filter.element_configuration != FilterElementConfiguration::StoreInRxBuffer;
+
+                if filter.id1 > MAX_EXTENDED_ID
+                    || (id2_is_id_or_mask && filter.id2 > MAX_EXTENDED_ID)
+                {
+                    return Err(FilterError::InvalidId);
+                }
+
+                self.check_filter_rx_buffer(filter)?;
+
+                self.get_extended_filter_element(filter.number).set(filter);
+
+                Ok(())
+            }
+
+            /// A filter storing into a dedicated Rx buffer must use one of the Rx buffers
+            /// reserved by the [`NodeConfig::ram_layout`]
+            fn check_filter_rx_buffer(&self, filter: &Filter) -> Result<(), FilterError> {
+                match &self.ram_layout {
+                    Some(layout)
+                        if filter.element_configuration
+                            == FilterElementConfiguration::StoreInRxBuffer
+                            && u8::from(filter.rx_buffer_offset)
+                                >= layout.requirements.rx_buffers =>
+                    {
+                        Err(FilterError::InvalidRxBuffer)
+                    }
+                    _ => Ok(()),
+                }
+            }
+
+            /// Set the global AND mask (XIDAM) applied to extended IDs before range filtering.
+            /// The reset value (all ones) leaves the received ID unchanged.
+            pub fn set_extended_id_mask(&self, mask: u32) -> Result<(), FilterError> {
+                if mask > MAX_EXTENDED_ID {
+                    return Err(FilterError::InvalidId);
+                }
+
+                self.effects.set_extended_id_and_mask(mask);
+
+                Ok(())
+            }
+
+            /// Connect the node to a transmit and a receive pin. The pins are consumed, so they
+            /// cannot be used as GPIOs anymore. Only the pins which can be routed to this node are
+            /// accepted, see [`crate::gpio::alt`].
+            pub fn setup_pins(
+                &self,
+                tx: impl Into<<$ModuleId as CanPins<I>>::Tx>,
+                rx: impl Into<<$ModuleId as CanPins<I>>::Rx>,
+            ) where
+                $ModuleId: CanPins<I>,
+            {
+                let mut rx: <$ModuleId as CanPins<I>>::Rx = rx.into();
+                rx.set_internal_resistor(Pull::Up);
+                rx.set_speed(Speed::High);
+                self.effects.connect_pin_rx(rx.rx_select());
+
+                let mut tx: <$ModuleId as CanPins<I>>::Tx = tx.into();
+                tx.set_speed(Speed::High);
+            }
+
+            /// Connect the node to the loop back bus of the module instead of pins, so the nodes
+            /// of the module can communicate with each other without a transceiver
+            pub fn setup_loopback(&self) {
+                self.effects.enable_loopback();
             }
 
             pub fn setup_interrupt(&self, interrupt: &NodeInterruptConfig) {
@@ -308,6 +637,26 @@ macro_rules! impl_can_node {
                 );
             }
 
+            fn get_standard_filter_element(&self, number: u8) -> StdFilter {
+                let list_start_address =
+                    u32::from(self.effects.get_standard_filter_list_start_address());
+                let element_address = self.ram_base_address
+                    + list_start_address
+                    + u32::from(number) * STANDARD_FILTER_ELEMENT_SIZE;
+
+                StdFilter::new(element_address as *mut u8)
+            }
+
+            fn get_extended_filter_element(&self, number: u8) -> ExtFilter {
+                let list_start_address =
+                    u32::from(self.effects.get_extended_filter_list_start_address());
+                let element_address = self.ram_base_address
+                    + list_start_address
+                    + u32::from(number) * EXTENDED_FILTER_ELEMENT_SIZE;
+
+                ExtFilter::new(element_address as *mut u8)
+            }
+
             fn set_rx_fifo0(&self, data: FifoData) {
                 self.effects.set_rx_fifo0_data_field_size(data.field_size);
                 self.effects.set_rx_fifo0_start_address(data.start_address);
@@ -357,38 +706,106 @@ macro_rules! impl_can_node {
                 }
             }
 
-            fn configure_baud_rate(&self, baud_rate: &BitTimingConfig) {
+            fn configure_timestamp(&self, config: &TimestampConfig) {
+                // TCP is the prescaler minus one
+                let tcp = config.prescaler.clamp(1, 16) - 1;
+                self.effects
+                    .set_timestamp_counter_configuration(config.source.into(), tcp);
+            }
+
+            fn configure_operating_mode(&self, mode: OperatingMode) {
+                // (TEST, MON, ASM, LBCK)
+                let (test, mon, asm, loopback) = match mode {
+                    OperatingMode::Normal => (false, false, false, false),
+                    OperatingMode::InternalLoopback => (true, true, false, true),
+                    OperatingMode::ExternalLoopback => (true, false, false, true),
+                    OperatingMode::BusMonitoring => (false, true, false, false),
+                    OperatingMode::Restricted => (false, false, true, false),
+                };
+
+                self.effects.set_operating_mode(test, mon, asm);
+
+                // TEST register can be written only when CCCR.TEST is set
+                if test {
+                    self.effects.set_loopback_mode(loopback);
+                }
+            }
+
+            fn configure_global_filter(&self, global_filter: &GlobalFilterConfig) {
+                self.effects.set_global_filter_configuration(
+                    global_filter.non_matching_standard.into(),
+                    global_filter.non_matching_extended.into(),
+                    global_filter.reject_remote_standard,
+                    global_filter.reject_remote_extended,
+                );
+            }
+
+            fn configure_baud_rate(&self, baud_rate: &BitTimingConfig) -> Result<(), ConfigError> {
                 let bit_timing: NominalBitTiming = match baud_rate {
                     BitTimingConfig::Auto(baud_rate) => {
-                        let module_freq = crate::scu::ccu::get_mcan_frequency() as f32;
-                        calculate_bit_timing(
-                            module_freq,
-                            baud_rate.baud_rate,
-                            baud_rate.sample_point,
-                            baud_rate.sync_jump_width,
-                        )
+                        let module_freq = crate::scu::ccu::get_mcan_frequency();
+                        // The closest bit timing is used, whatever its bit rate error
+                        calculate_nominal_bit_timing(module_freq, baud_rate, u32::MAX)
+                            .map_err(|_| ConfigError::InvalidBitTiming)?
+                            .timing
                     }
                     BitTimingConfig::Manual(baud_rate) => *baud_rate,
                 };
 
+                if !bit_timing.is_valid() {
+                    return Err(ConfigError::InvalidBitTiming);
+                }
+
                 self.effects.set_nominal_bit_timing(&bit_timing);
+                Ok(())
             }
 
-            fn configure_fast_baud_rate(&self, baud_rate: &FastBitTimingConfig) {
+            fn configure_fast_baud_rate(
+                &self,
+                baud_rate: &FastBitTimingConfig,
+            ) -> Result<DataBitTiming, ConfigError> {
                 let bit_timing: DataBitTiming = match baud_rate {
                     FastBitTimingConfig::Auto(baud_rate) => {
-                        let module_freq = crate::scu::ccu::get_mcan_frequency() as f32;
-                        calculate_fast_bit_timing(
-                            module_freq,
-                            baud_rate.baud_rate,
-                            baud_rate.sample_point,
-                            baud_rate.sync_jump_width,
-                        )
+                        let module_freq = crate::scu::ccu::get_mcan_frequency();
+                        // The closest bit timing is used, whatever its bit rate error
+                        calculate_data_bit_timing(module_freq, baud_rate, u32::MAX)
+                            .map_err(|_| ConfigError::InvalidBitTiming)?
+                            .timing
                     }
                     FastBitTimingConfig::Manual(baud_rate) => *baud_rate,
                 };
 
+                if !bit_timing.is_valid() {
+                    return Err(ConfigError::InvalidBitTiming);
+                }
+
                 self.effects.set_data_bit_timing(&bit_timing);
+                Ok(bit_timing)
+            }
+
+            fn configure_transceiver_delay_compensation(
+                &self,
+                frame_mode: FrameMode,
+                data_bit_timing: DataBitTiming,
+                tdc: &TransceiverDelayCompensation,
+            ) -> Result<(), ConfigError> {
+                let supported = data_bit_timing.supports_transceiver_delay_compensation();
+
+                let enabled = match tdc.mode {
+                    TdcMode::Auto => frame_mode == FrameMode::FdLongAndFast && supported,
+                    TdcMode::Enabled if !supported => return Err(ConfigError::InvalidBitTiming),
+                    TdcMode::Enabled => true,
+                    TdcMode::Disabled => false,
+                };
+
+                let offset = tdc
+                    .offset
+                    .unwrap_or_else(|| data_bit_timing.sample_point_offset());
+
+                self.effects
+                    .set_transceiver_delay_compensation(enabled, offset, tdc.filter_window);
+
+                Ok(())
             }
 
             #[inline]
@@ -440,180 +857,943 @@ macro_rules! impl_can_node {
                     self.effects.set_interrupt_routing_group_2(line, group);
                 }
             }
+        }
 
-            fn connect_pin_rx(
-                &self,
-                rxd: &RxdIn<$ModuleId, I>,
-                mode: InputMode,
-                pad_driver: PadDriver,
-            ) {
-                let port = Port::new(rxd.port);
-                port.set_pin_mode_input(rxd.pin_index, mode);
-                port.set_pin_pad_driver(rxd.pin_index, pad_driver);
-                self.effects.connect_pin_rx(rxd.select);
+        // Methods only valid on a configured node
+        impl<I: NodeId> Node<$NodeReg, $ModuleReg, I, Configured> {
+            /// Stop the bus activity and enable the configuration change again, for example to
+            /// change the bit rate at runtime. Pending transfers are completed first, then the
+            /// Tx requests and the Rx FIFO states are reset by the hardware.
+            #[must_use]
+            pub fn unlock_configuration(self) -> Node<$NodeReg, $ModuleReg, I, Configurable> {
+                self.effects.enable_configuration_change();
+                self.into_state()
             }
 
-            fn connect_pin_tx(
-                &self,
-                txd: &TxdOut<$ModuleId, I>,
-                mode: OutputMode,
-                pad_driver: PadDriver,
-            ) {
-                let port = Port::new(txd.port);
-                port.set_pin_mode_output(txd.pin_index, mode, txd.select);
-                port.set_pin_pad_driver(txd.pin_index, pad_driver);
-                port.set_pin_low(txd.pin_index);
+            /// Enter the power down mode: pending transfers are completed, then the bus activity
+            /// is stopped and the node clock can be switched off.
+            #[must_use]
+            pub fn stop_clock(self) -> Node<$NodeReg, $ModuleReg, I, ClockStopped> {
+                self.effects.request_clock_stop();
+                self.into_state()
             }
-        }
 
-        // Methods only valid on a configured node
-        impl<I: NodeId> Node<$NodeReg, $ModuleReg, I, Configured> {
             // TODO This does not feel to be the right place for this function
             pub fn clear_interrupt_flag(&self, interrupt: Interrupt) {
                 self.effects.clear_interrupt_flag(interrupt);
             }
 
+            /// Transmit a frame using the Tx FIFO or queue
             pub fn transmit(&self, frame: &Frame) -> Result<(), TransmitError> {
-                let buffer_id = self.get_tx_fifo_queue_put_index();
-                self.transmit_inner(buffer_id, frame.id, false, false, false, frame.data)
+                let buffer_id = self.get_tx_fifo_queue_buffer()?;
+                self.transmit_borrowed_frame(buffer_id, frame)
             }
 
-            pub fn receive(&self, from: ReadFrom, data: &mut [u8]) -> Option<RxMessage> {
-                let Some(rx_config) = self.rx_config else {
-                    return None;
-                };
+            /// Transmit a frame using a dedicated Tx buffer
+            pub fn transmit_to(
+                &self,
+                buffer: TxBufferId,
+                frame: &Frame,
+            ) -> Result<(), TransmitError> {
+                self.check_dedicated_tx_buffer(buffer)?;
+                self.transmit_borrowed_frame(buffer, frame)
+            }
 
-                let buffer_id = match from {
-                    ReadFrom::RxFifo0 => self.effects.get_rx_fifo0_get_index(),
-                    ReadFrom::RxFifo1 => self.effects.get_rx_fifo1_get_index(),
-                    ReadFrom::Buffer(id) => id,
-                };
+            /// Transmit an owned frame using the Tx FIFO or queue, honoring its FD, BRS, ESI and RTR flags
+            pub fn transmit_frame(&self, frame: &OwnedFrame) -> Result<(), TransmitError> {
+                let buffer_id = self.get_tx_fifo_queue_buffer()?;
+                self.transmit_owned_frame(buffer_id, frame)
+            }
 
-                let rx_buf_elem = self.effects.get_rx_element_address(
-                    self.ram_base_address,
-                    match from {
-                        ReadFrom::RxFifo0 => rx_config.rx_fifo0_start_address,
-                        ReadFrom::RxFifo1 => rx_config.rx_fifo1_start_address,
-                        ReadFrom::Buffer(_) => rx_config.rx_buffers_start_address,
-                    },
-                    from,
-                    buffer_id,
-                );
+            /// Transmit an owned frame using a dedicated Tx buffer, honoring its FD, BRS, ESI and RTR flags
+            pub fn transmit_frame_to(
+                &self,
+                buffer: TxBufferId,
+                frame: &OwnedFrame,
+            ) -> Result<(), TransmitError> {
+                self.check_dedicated_tx_buffer(buffer)?;
+                self.transmit_owned_frame(buffer, frame)
+            }
 
-                // info!("read message on buffer_id: {}", buffer_id.0);
-                // info!("rx_buf_elem at: {:x}", rx_buf_elem.get_ptr());
+            /// Transmit a remote frame (RTR) using the Tx FIFO or queue, requesting `len` data
+            /// bytes to the node which owns `id`. Remote frames are always classic CAN frames.
+            pub fn transmit_remote_request(
+                &self,
+                id: MessageId,
+                len: usize,
+            ) -> Result<(), TransmitError> {
+                if len > 8 {
+                    return Err(TransmitError::InvalidDataLength);
+                }
+                let data_length_code =
+                    DataLenghtCode::from_length(len).ok_or(TransmitError::InvalidDataLength)?;
 
-                let id = MessageId {
-                    data: rx_buf_elem.get_message_id(),
-                    length: rx_buf_elem.get_message_id_length(),
+                let message = TxMessage {
+                    id,
+                    buffer_id: Some(self.get_tx_fifo_queue_buffer()?),
+                    remote_transmit_request: true,
+                    tx_event_fifo_control: self.tx_event_fifo_control(),
+                    data_length_code,
+                    frame_mode: FrameMode::Standard,
+                    ..Default::default()
                 };
 
-                let data_length_code = rx_buf_elem.get_data_length();
-                let frame_mode = rx_buf_elem.get_frame_mode();
+                self.transmit_inner(&message, &[])
+            }
 
-                rx_buf_elem.read_data(data_length_code, data.as_mut_ptr());
+            /// Write the data frame answering the remote requests for `id` into a dedicated Tx
+            /// buffer, without transmitting it. Store the remote requests in a dedicated Rx
+            /// buffer with [`Filter::store_in_rx_buffer`] (the global filter must not reject
+            /// remote frames), then send the answer with [`Self::answer_remote_request`] or
+            /// [`Self::send_remote_answer`].
+            /// The answer can be updated at any time while it is not being transmitted.
+            pub fn set_remote_answer(
+                &self,
+                buffer: TxBufferId,
+                id: MessageId,
+                data: &[u8],
+            ) -> Result<(), TransmitError> {
+                self.check_dedicated_tx_buffer(buffer)?;
 
-                match from {
-                    ReadFrom::RxFifo0 => self.effects.set_rx_fifo0_acknowledge_index(buffer_id),
-                    ReadFrom::RxFifo1 => self.effects.set_rx_fifo1_acknowledge_index(buffer_id),
-                    ReadFrom::Buffer(_) => (),
+                // Remote requests are classic CAN frames, so is the answer
+                if data.len() > 8 {
+                    return Err(TransmitError::InvalidDataLength);
                 }
+                let data_length_code = DataLenghtCode::from_length(data.len())
+                    .ok_or(TransmitError::InvalidDataLength)?;
 
-                self.effects.clear_rx_buffer_new_data_flag(buffer_id);
-
-                Some(RxMessage {
+                let message = TxMessage {
                     id,
+                    buffer_id: Some(buffer),
+                    tx_event_fifo_control: self.tx_event_fifo_control(),
                     data_length_code,
-                    frame_mode,
-                    buffer_id,
-                    from,
-                })
-            }
+                    frame_mode: FrameMode::Standard,
+                    ..Default::default()
+                };
 
-            fn get_tx_fifo_queue_put_index(&self) -> TxBufferId {
-                let id = self.effects.get_tx_fifo_queue_put_index() & 0x1F;
-                // SAFETY: The value is in range because it is read from a register and masked with 0x1F
-                unsafe { TxBufferId::try_from(id).unwrap_unchecked() }
+                self.write_tx_element(&message, data).map(|_| ())
             }
 
-            #[allow(unused_variables)]
-            fn transmit_inner(
+            /// Transmit the answer written with [`Self::set_remote_answer`] into the dedicated Tx
+            /// buffer `answer` if the dedicated Rx buffer `request` holds a remote request.
+            /// The content of `request` is always consumed, so the next request can be stored.
+            /// Returns whether the answer has been sent.
+            /// Do not use it on a node whose Rx interrupts are handled by
+            /// [`Self::handle_rx_interrupt`]: the handler moves the content of all the dedicated
+            /// Rx buffers to its queue, so it can consume the request first, or read it while it
+            /// is released here. With an [`RxQueue`], answer the remote frames popped from the
+            /// queue with [`Self::send_remote_answer`] instead.
+            pub fn answer_remote_request(
                 &self,
-                buffer_id: TxBufferId,
-                id: MessageId,
-                tx_event_fifo_control: bool,
-                remote_transmit_request: bool,
-                error_state_indicator: bool,
-                data: &[u8],
-            ) -> Result<(), TransmitError> {
-                let req_pending = self.effects.is_tx_buffer_request_pending(buffer_id);
-                if req_pending {
-                    return Err(TransmitError::Busy);
+                request: RxBufferId,
+                answer: TxBufferId,
+            ) -> Result<bool, TransmitError> {
+                self.check_dedicated_tx_buffer(answer)?;
+
+                // A dedicated Rx buffer has no overwrite mode, no message is dropped here
+                let mut lost = false;
+                let (rx_buf_elem, buffer_id) =
+                    match self.get_rx_element(ReadFrom::Buffer(request), &mut lost) {
+                        Ok(element) => element,
+                        Err(ReceiveError::NoNewData) => return Ok(false),
+                        Err(_) => return Err(TransmitError::NotConfigured),
+                    };
+
+                let is_remote_request = rx_buf_elem.is_remote_frame();
+                self.release_rx_element(ReadFrom::Buffer(request), buffer_id);
+
+                if !is_remote_request {
+                    return Ok(false);
                 }
 
-                let tx_buf_el = self.get_tx_element_address(self.ram_base_address, buffer_id);
+                self.send_remote_answer(answer)?;
+                Ok(true)
+            }
 
-                tx_buf_el.set_msg_id(id);
+            /// Transmit the answer written with [`Self::set_remote_answer`] into the dedicated Tx
+            /// buffer `answer`, for a remote request received by other means than
+            /// [`Self::answer_remote_request`], e.g. popped from an [`RxQueue`].
+            /// Nothing is done if the answer is already pending, it serves this request too.
+            pub fn send_remote_answer(&self, answer: TxBufferId) -> Result<(), TransmitError> {
+                self.check_dedicated_tx_buffer(answer)?;
 
-                if tx_event_fifo_control {
-                    tx_buf_el.set_tx_event_fifo_ctrl(tx_event_fifo_control);
-                    tx_buf_el.set_message_marker(buffer_id);
+                if !self.effects.is_tx_buffer_request_pending(answer) {
+                    self.effects.set_tx_buffer_add_request(answer.into());
                 }
 
-                tx_buf_el.set_remote_transmit_req(remote_transmit_request);
+                Ok(())
+            }
+
+            /// Receive a message, copying its data into `data`.
+            /// If `data` is shorter than the frame, nothing is read and the message is left in the
+            /// message RAM, so it can be read again with a larger buffer.
+            pub fn receive(
+                &self,
+                from: ReadFrom,
+                data: &mut [u8],
+            ) -> Result<RxMessage, ReceiveError> {
+                self.receive_inner(from, data, false)
+            }
+
+            /// Receive a message, copying at most `data.len()` bytes into `data`.
+            /// Exceeding bytes are dropped: compare `RxMessage::len` with `data.len()` to detect it.
+            pub fn receive_truncated(
+                &self,
+                from: ReadFrom,
+                data: &mut [u8],
+            ) -> Result<RxMessage, ReceiveError> {
+                self.receive_inner(from, data, true)
+            }
+
+            /// Receive an owned frame, including its flags, timestamp and filter index
+            pub fn receive_frame(&self, from: ReadFrom) -> Result<OwnedFrame, ReceiveError> {
+                let mut lost = false;
+                self.read_rx_frame(from, &mut lost)
+            }
 
-                if let FrameMode::FdLong | FrameMode::FdLongAndFast = self.frame_mode {
-                    tx_buf_el.set_err_state_indicator(error_state_indicator)
+            /// Read all the messages of an Rx FIFO (or a dedicated Rx buffer) and pass them to
+            /// `f`, oldest first. Returns the number of messages read and whether messages have
+            /// been lost, a lost message does not stop the reading. In overwrite mode, the
+            /// messages dropped from a full FIFO or overwritten while being read are reported
+            /// as lost.
+            pub fn drain_fifo(
+                &self,
+                from: ReadFrom,
+                mut f: impl FnMut(OwnedFrame),
+            ) -> Result<DrainReport, ReceiveError> {
+                let mut report = DrainReport::default();
+                loop {
+                    match self.read_rx_frame(from, &mut report.message_lost) {
+                        Ok(frame) => {
+                            f(frame);
+                            report.frames += 1;
+                        }
+                        Err(ReceiveError::FifoEmpty | ReceiveError::NoNewData) => {
+                            return Ok(report)
+                        }
+                        // The message lost flag has been cleared, the FIFO can be read again
+                        Err(ReceiveError::MessageLost) => report.message_lost = true,
+                        Err(error) => return Err(error),
+                    }
                 }
+            }
 
-                let dlc = DataLenghtCode::from_length(data.len())
-                    .ok_or(TransmitError::InvalidDataLength)?;
+            /// Transmit an owned frame using the Tx FIFO or queue, waiting until an element is free.
+            /// The task is woken by [`wake_node`], which must be called from the interrupt handler
+            /// of the line the transmission completed interrupt is routed to. The handler is also
+            /// in charge of clearing the TC flag, see [`Self::clear_interrupt_flag`].
+            /// Only one task at a time can wait for a transmission on a node: there is a single
+            /// waker per node, a second waiting task is not woken.
+            /// Dropping the future before completion does not transmit the frame.
+            pub async fn transmit_async(&self, frame: &OwnedFrame) -> Result<(), TransmitError> {
+                poll_fn(|cx| {
+                    if let Some(waker) = TX_WAKERS.get(waker_index::<$ModuleId, I>()) {
+                        waker.register(cx.waker());
+                    }
 
-                tx_buf_el.set_data_length(dlc);
-                tx_buf_el.write_tx_buf_data(dlc, data.as_ptr());
-                tx_buf_el.set_frame_mode_req(self.frame_mode);
-                let buffer_id = 0; //TODO
-                self.effects.set_tx_buffer_add_request(buffer_id);
+                    match self.transmit_frame(frame) {
+                        Err(TransmitError::Busy) => Poll::Pending,
+                        result => Poll::Ready(result),
+                    }
+                })
+                .await
+            }
 
-                info!("transmit {}#{}", id.data, crate::log::HexSlice::from(data));
+            /// Receive an owned frame from the first non empty Rx FIFO, waiting until one is received.
+            /// The task is woken by [`wake_node`], which must be called from the interrupt handler
+            /// of the line the new message interrupts are routed to. The handler is also in charge
+            /// of clearing the RF0N and RF1N flags, see [`Self::clear_interrupt_flag`].
+            /// Only one task at a time can wait for a frame on a node: there is a single waker per
+            /// node, a second waiting task is not woken.
+            /// Dropping the future before completion does not lose any frame.
+            pub async fn receive_async(&self) -> Result<OwnedFrame, ReceiveError> {
+                poll_fn(|cx| {
+                    if let Some(waker) = RX_WAKERS.get(waker_index::<$ModuleId, I>()) {
+                        waker.register(cx.waker());
+                    }
 
-                Ok(())
+                    match self.receive_owned_frame() {
+                        Err(ReceiveError::FifoEmpty) => Poll::Pending,
+                        result => Poll::Ready(result),
+                    }
+                })
+                .await
             }
 
-            fn get_tx_element_address(
-                &self,
-                ram_base_address: u32,
-                buffer_number: TxBufferId,
-            ) -> Tx {
-                let num_of_config_bytes = 8u32;
-                let num_of_data_bytes = self.effects.get_tx_buffer_data_field_size() as u32;
-                let tx_buffer_size = num_of_config_bytes + num_of_data_bytes;
-                let tx_buffer_index = tx_buffer_size * u32::from(u8::from(buffer_number));
+            /// New data flags of the dedicated Rx buffers, to find the buffers holding a message
+            /// which has not been read yet
+            pub fn rx_buffers_new_data(&self) -> RxBuffersNewData {
+                RxBuffersNewData::new(self.effects.get_rx_buffers_new_data())
+            }
 
-                let tx_buffer_element_address =
-                    ram_base_address + TX_BUFFER_START_ADDRESS + tx_buffer_index;
+            /// Read the frame stored in a dedicated Rx buffer and clear its new data flag.
+            /// The hardware does not store a frame in a buffer whose new data flag is set, so
+            /// the frame cannot change while it is read: the next matching frame is stored only
+            /// after the flag is cleared.
+            pub fn take_rx_buffer(&self, buffer: RxBufferId) -> Result<OwnedFrame, ReceiveError> {
+                self.receive_frame(ReadFrom::Buffer(buffer))
+            }
 
-                Tx::new(tx_buffer_element_address as *mut u8)
+            /// Number of messages stored in Rx FIFO 0
+            pub fn rx_fifo0_fill_level(&self) -> u8 {
+                self.effects.get_rx_fifo0_fill_level()
             }
 
-            // TODO Untested, example missing
-            #[inline]
-            fn is_tx_buffer_cancellation_finished(&self, tx_buffer_id: TxBufferId) -> bool {
-                self.is_tx_buffer_transmission_occured(tx_buffer_id)
+            /// Number of messages stored in Rx FIFO 1
+            pub fn rx_fifo1_fill_level(&self) -> u8 {
+                self.effects.get_rx_fifo1_fill_level()
             }
 
-            // TODO Untested, example missing
-            #[inline]
-            fn is_tx_buffer_transmission_occured(&self, tx_buffer_id: TxBufferId) -> bool {
-                self.effects
-                    .is_tx_buffer_transmission_occured(tx_buffer_id.into())
+            /// Fill level, full, message lost and watermark state of Rx FIFO 0
+            pub fn rx_fifo0_status(&self) -> RxFifoStatus {
+                let watermark_level = self.rx_config.map_or(0, |c| c.fifo0_watermark_level);
+                RxFifoStatus::from_register(self.effects.get_rx_fifo0_status(), watermark_level)
             }
-        }
-    };
-}
 
-impl_can_node!(
-    crate::pac::can0::Can0,
+            /// Fill level, full, message lost and watermark state of Rx FIFO 1
+            pub fn rx_fifo1_status(&self) -> RxFifoStatus {
+                let watermark_level = self.rx_config.map_or(0, |c| c.fifo1_watermark_level);
+                RxFifoStatus::from_register(self.effects.get_rx_fifo1_status(), watermark_level)
+            }
+
+            /// Interrupt helper: read the interrupt flags and clear the new message flags (RF0N,
+            /// RF1N, DRX), then move the received frames from Rx FIFO 0, Rx FIFO 1 and the
+            /// dedicated Rx buffers to `queue`. The message lost flags are cleared when the lost
+            /// message is detected, the other flags are left set for the caller.
+            /// Messages dropped or overwritten in overwrite mode are reported by setting the
+            /// message lost flag of their FIFO in the returned flags.
+            /// When the queue is full, the remaining frames are left in the message RAM and are
+            /// moved on the next call.
+            /// Returns the flags which were set, so the caller can handle the other interrupts.
+            pub fn handle_rx_interrupt<const N: usize>(
+                &self,
+                queue: &RxQueue<N>,
+            ) -> InterruptFlags {
+                let mut flags = InterruptFlags(self.effects.get_interrupt_flags());
+                // Only the flags read above are cleared, flags set in the meantime stay pending
+                self.effects
+                    .clear_interrupt_flags(flags.0 & RX_NEW_MESSAGE_FLAGS);
+
+                if self.drain_rx_into(ReadFrom::RxFifo0, queue, &mut flags)
+                    && self.drain_rx_into(ReadFrom::RxFifo1, queue, &mut flags)
+                {
+                    for buffer in self.rx_buffers_new_data() {
+                        if !self.drain_rx_into(ReadFrom::Buffer(buffer), queue, &mut flags) {
+                            break;
+                        }
+                    }
+                }
+
+                flags
+            }
+
+            /// Move frames from `from` to `queue` until `from` is empty, lost messages are
+            /// recorded in `flags`.
+            /// Returns false if the queue is full.
+            fn drain_rx_into<const N: usize>(
+                &self,
+                from: ReadFrom,
+                queue: &RxQueue<N>,
+                flags: &mut InterruptFlags,
+            ) -> bool {
+                let mut lost = false;
+                let queue_not_full = loop {
+                    match self.get_rx_element(from, &mut lost) {
+                        Ok((rx_buf_elem, buffer_id)) => {
+                            let frame = rx_buf_elem.read_frame();
+                            if self.is_rx_element_overwritten(from, buffer_id) {
+                                lost = true;
+                                continue;
+                            }
+                            // The element is released only once the frame is in the queue
+                            if queue.push(frame).is_err() {
+                                break false;
+                            }
+                            self.release_rx_element(from, buffer_id);
+                        }
+                        // The message lost flag has been cleared, the FIFO can be read again
+                        Err(ReceiveError::MessageLost) => lost = true,
+                        Err(_) => break true,
+                    }
+                };
+
+                if lost {
+                    flags.insert_message_lost(from);
+                }
+                queue_not_full
+            }
+
+            /// Read the next frame of `from`, `lost` is set when messages have been dropped or
+            /// overwritten in overwrite mode.
+            fn read_rx_frame(
+                &self,
+                from: ReadFrom,
+                lost: &mut bool,
+            ) -> Result<OwnedFrame, ReceiveError> {
+                loop {
+                    let (rx_buf_elem, buffer_id) = self.get_rx_element(from, lost)?;
+
+                    let frame = rx_buf_elem.read_frame();
+
+                    // In overwrite mode the element is read again if it was overwritten meanwhile
+                    if self.is_rx_element_overwritten(from, buffer_id) {
+                        *lost = true;
+                        continue;
+                    }
+
+                    self.release_rx_element(from, buffer_id);
+                    return Ok(frame);
+                }
+            }
+
+            /// Receive from the first non empty Rx FIFO
+            fn receive_owned_frame(&self) -> Result<OwnedFrame, ReceiveError> {
+                match self.receive_frame(ReadFrom::RxFifo0) {
+                    Err(ReceiveError::FifoEmpty) => self.receive_frame(ReadFrom::RxFifo1),
+                    result => result,
+                }
+            }
+
+            fn receive_inner(
+                &self,
+                from: ReadFrom,
+                data: &mut [u8],
+                truncate: bool,
+            ) -> Result<RxMessage, ReceiveError> {
+                // Single reads do not report the messages dropped in overwrite mode
+                let mut lost = false;
+                loop {
+                    let (rx_buf_elem, buffer_id) = self.get_rx_element(from, &mut lost)?;
+
+                    let id = MessageId {
+                        data: rx_buf_elem.get_message_id(),
+                        length: rx_buf_elem.get_message_id_length(),
+                    };
+
+                    let data_length_code = rx_buf_elem.get_data_length();
+                    let frame_mode = rx_buf_elem.get_frame_mode();
+
+                    // Remote frames do not carry data, their DLC is only the requested length
+                    let remote_transmit_request = rx_buf_elem.is_remote_frame();
+                    let len = rx_buf_elem.get_stored_data_length();
+
+                    let copy_len = if truncate { len.min(data.len()) } else { len };
+
+                    let dest = data
+                        .get_mut(..copy_len)
+                        .ok_or(ReceiveError::BufferTooSmall { required: len })?;
+
+                    rx_buf_elem.read_data(dest);
+
+                    let timestamp = rx_buf_elem.get_timestamp();
+
+                    // In overwrite mode the element is read again if it was overwritten meanwhile
+                    if self.is_rx_element_overwritten(from, buffer_id) {
+                        continue;
+                    }
+
+                    self.release_rx_element(from, buffer_id);
+
+                    return Ok(RxMessage {
+                        id,
+                        data_length_code,
+                        remote_transmit_request,
+                        len,
+                        timestamp,
+                        frame_mode,
+                        buffer_id,
+                        from,
+                    });
+                }
+            }
+
+            /// `lost` is set when the oldest message of a full FIFO is dropped in overwrite mode.
+            fn get_rx_element(
+                &self,
+                from: ReadFrom,
+                lost: &mut bool,
+            ) -> Result<(Rx, RxBufferId), ReceiveError> {
+                let rx_config = self.rx_config.ok_or(ReceiveError::NotConfigured)?;
+
+                let buffer_id = match from {
+                    ReadFrom::RxFifo0 => {
+                        if rx_config.fifo0_operating_mode == RxFifoMode::Overwrite
+                            && self.release_full_fifo_head(from)
+                        {
+                            *lost = true;
+                        }
+                        if self.effects.is_rx_fifo0_message_lost() {
+                            self.effects
+                                .clear_interrupt_flag(Interrupt::RxFifo0messageLost);
+                            return Err(ReceiveError::MessageLost);
+                        }
+                        if self.effects.get_rx_fifo0_fill_level() == 0 {
+                            return Err(ReceiveError::FifoEmpty);
+                        }
+                        self.effects.get_rx_fifo0_get_index()
+                    }
+                    ReadFrom::RxFifo1 => {
+                        if rx_config.fifo1_operating_mode == RxFifoMode::Overwrite
+                            && self.release_full_fifo_head(from)
+                        {
+                            *lost = true;
+                        }
+                        if self.effects.is_rx_fifo1_message_lost() {
+                            self.effects
+                                .clear_interrupt_flag(Interrupt::RxFifo1messageLost);
+                            return Err(ReceiveError::MessageLost);
+                        }
+                        if self.effects.get_rx_fifo1_fill_level() == 0 {
+                            return Err(ReceiveError::FifoEmpty);
+                        }
+                        self.effects.get_rx_fifo1_get_index()
+                    }
+                    ReadFrom::Buffer(id) => {
+                        if !self.effects.is_rx_buffer_new_data_updated(id.into()) {
+                            return Err(ReceiveError::NoNewData);
+                        }
+                        id
+                    }
+                };
+
+                let rx_buf_elem = self.effects.get_rx_element_address(
+                    self.ram_base_address,
+                    match from {
+                        ReadFrom::RxFifo0 => rx_config.rx_fifo0_start_address,
+                        ReadFrom::RxFifo1 => rx_config.rx_fifo1_start_address,
+                        ReadFrom::Buffer(_) => rx_config.rx_buffers_start_address,
+                    },
+                    from,
+                    buffer_id,
+                );
+
+                Ok((rx_buf_elem, buffer_id))
+            }
+
+            /// In overwrite mode a message received while the FIFO is full is stored in the
+            /// element at the get index, so the oldest message of a full FIFO is dropped before
+            /// reading: the hardware then writes the next message in the freed element.
+            /// Returns true if a message has been dropped.
+            fn release_full_fifo_head(&self, from: ReadFrom) -> bool {
+                match from {
+                    ReadFrom::RxFifo0 => {
+                        let full = self.rx_fifo0_status().full;
+                        if full {
+                            let get_index = self.effects.get_rx_fifo0_get_index();
+                            self.effects.set_rx_fifo0_acknowledge_index(get_index);
+                        }
+                        full
+                    }
+                    ReadFrom::RxFifo1 => {
+                        let full = self.rx_fifo1_status().full;
+                        if full {
+                            let get_index = self.effects.get_rx_fifo1_get_index();
+                            self.effects.set_rx_fifo1_acknowledge_index(get_index);
+                        }
+                        full
+                    }
+                    ReadFrom::Buffer(_) => false,
+                }
+            }
+
+            /// In overwrite mode the hardware moves the get index forward when it overwrites the
+            /// oldest message: an element is valid only if it is still at the get index after
+            /// being read.
+            fn is_rx_element_overwritten(&self, from: ReadFrom, buffer_id: RxBufferId) -> bool {
+                let Some(rx_config) = self.rx_config else {
+                    return false;
+                };
+
+                match from {
+                    ReadFrom::RxFifo0 => {
+                        rx_config.fifo0_operating_mode == RxFifoMode::Overwrite
+                            && self.effects.get_rx_fifo0_get_index() != buffer_id
+                    }
+                    ReadFrom::RxFifo1 => {
+                        rx_config.fifo1_operating_mode == RxFifoMode::Overwrite
+                            && self.effects.get_rx_fifo1_get_index() != buffer_id
+                    }
+                    ReadFrom::Buffer(_) => false,
+                }
+            }
+
+            fn release_rx_element(&self, from: ReadFrom, buffer_id: RxBufferId) {
+                match from {
+                    ReadFrom::RxFifo0 => self.effects.set_rx_fifo0_acknowledge_index(buffer_id),
+                    ReadFrom::RxFifo1 => self.effects.set_rx_fifo1_acknowledge_index(buffer_id),
+                    // FIFO elements have no new data flag, the index would select a dedicated buffer
+                    ReadFrom::Buffer(_) => self.effects.clear_rx_buffer_new_data_flag(buffer_id),
+                }
+            }
+
+            fn transmit_borrowed_frame(
+                &self,
+                buffer_id: TxBufferId,
+                frame: &Frame,
+            ) -> Result<(), TransmitError> {
+                let data_length_code = DataLenghtCode::from_length(frame.data.len())
+                    .ok_or(TransmitError::InvalidDataLength)?;
+
+                let message = TxMessage {
+                    id: frame.id,
+                    buffer_id: Some(buffer_id),
+                    tx_event_fifo_control: self.tx_event_fifo_control(),
+                    data_length_code,
+                    frame_mode: self.frame_mode,
+                    ..Default::default()
+                };
+
+                self.transmit_inner(&message, frame.data)
+            }
+
+            fn transmit_owned_frame(
+                &self,
+                buffer_id: TxBufferId,
+                frame: &OwnedFrame,
+            ) -> Result<(), TransmitError> {
+                use embedded_can::Frame as _;
+
+                if !self.supports_frame_mode(frame.frame_mode()) {
+                    return Err(TransmitError::InvalidFrameMode);
+                }
+
+                let data_length_code = frame
+                    .data_length_code()
+                    .ok_or(TransmitError::InvalidDataLength)?;
+
+                let message = TxMessage {
+                    id: frame.message_id(),
+                    buffer_id: Some(buffer_id),
+                    remote_transmit_request: frame.is_remote_frame(),
+                    error_state_indicator: frame.error_state_indicator(),
+                    tx_event_fifo_control: self.tx_event_fifo_control(),
+                    data_length_code,
+                    frame_mode: frame.frame_mode(),
+                    ..Default::default()
+                };
+
+                self.transmit_inner(&message, frame.data())
+            }
+
+            fn is_tx_event_fifo_enabled(tx_config: &TxConfig) -> bool {
+                (1..=32).contains(&tx_config.event_fifo_size)
+            }
+
+            /// Store Tx events only if the Tx event FIFO has been configured
+            fn tx_event_fifo_control(&self) -> bool {
+                self.tx_config
+                    .as_ref()
+                    .is_some_and(Self::is_tx_event_fifo_enabled)
+            }
+
+            /// Buffer pointed by the Tx FIFO/queue put index. The put index is not valid while the
+            /// FIFO/queue is full (TXFQS.TFQF), in that case the request is rejected as busy.
+            fn get_tx_fifo_queue_buffer(&self) -> Result<TxBufferId, TransmitError> {
+                let tx_config = self.tx_config.ok_or(TransmitError::NotConfigured)?;
+
+                match tx_config.mode {
+                    TxMode::Fifo | TxMode::Queue | TxMode::SharedFifo | TxMode::SharedQueue => {
+                        if self.effects.is_tx_fifo_queue_full() {
+                            return Err(TransmitError::Busy);
+                        }
+                        Ok(self.get_tx_fifo_queue_put_index())
+                    }
+                    TxMode::DedicatedBuffers => Err(TransmitError::InvalidBuffer),
+                }
+            }
+
+            /// Dedicated Tx buffers are placed before the Tx FIFO/queue buffers
+            fn check_dedicated_tx_buffer(&self, buffer: TxBufferId) -> Result<(), TransmitError> {
+                let tx_config = self.tx_config.ok_or(TransmitError::NotConfigured)?;
+
+                let dedicated_buffers = match tx_config.mode {
+                    TxMode::DedicatedBuffers | TxMode::SharedFifo | TxMode::SharedQueue => {
+                        tx_config.dedicated_tx_buffers_number
+                    }
+                    TxMode::Fifo | TxMode::Queue => 0,
+                };
+
+                if u8::from(buffer) < dedicated_buffers {
+                    Ok(())
+                } else {
+                    Err(TransmitError::InvalidBuffer)
+                }
+            }
+
+            /// A frame can be sent only if the node has been configured for its frame mode
+            fn supports_frame_mode(&self, frame_mode: FrameMode) -> bool {
+                match frame_mode {
+                    FrameMode::Standard => true,
+                    FrameMode::FdLong => self.frame_mode != FrameMode::Standard,
+                    FrameMode::FdLongAndFast => self.frame_mode == FrameMode::FdLongAndFast,
+                }
+            }
+
+            fn get_tx_fifo_queue_put_index(&self) -> TxBufferId {
+                let id = self.effects.get_tx_fifo_queue_put_index() & 0x1F;
+                // SAFETY: The value is in range because it is read from a register and masked with 0x1F
+                unsafe { TxBufferId::try_from(id).unwrap_unchecked() }
+            }
+
+            fn transmit_inner(
+                &self,
+                message: &TxMessage,
+                data: &[u8],
+            ) -> Result<(), TransmitError> {
+                let buffer_id = self.write_tx_element(message, data)?;
+                self.effects.set_tx_buffer_add_request(buffer_id.into());
+
+                info!(
+                    "transmit {}#{}",
+                    message.id.data,
+                    crate::log::HexSlice::from(data)
+                );
+
+                Ok(())
+            }
+
+            /// Write a message into its Tx buffer, without requesting its transmission
+            fn write_tx_element(
+                &self,
+                message: &TxMessage,
+                data: &[u8],
+            ) -> Result<TxBufferId, TransmitError> {
+                let buffer_id = message
+                    .buffer_id
+                    .map_or_else(|| self.get_tx_fifo_queue_buffer(), Ok)?;
+
+                // Remote frames do not carry data, the DLC is only the requested length
+                if !message.remote_transmit_request
+                    && data.len() != message.data_length_code.to_length()
+                {
+                    return Err(TransmitError::InvalidDataLength);
+                }
+
+                let tx_config = self.tx_config.ok_or(TransmitError::NotConfigured)?;
+
+                // A longer frame would be written into the next elements of the message RAM
+                let data_field_size = usize::from(tx_config.buffer_data_field_size.to_bytes());
+                if !message.remote_transmit_request && data.len() > data_field_size {
+                    return Err(TransmitError::InvalidDataLength);
+                }
+
+                let req_pending = self.effects.is_tx_buffer_request_pending(buffer_id);
+                if req_pending {
+                    return Err(TransmitError::Busy);
+                }
+
+                let tx_buf_el = self.get_tx_element_address(
+                    self.ram_base_address,
+                    tx_config.tx_buffers_start_address,
+                    buffer_id,
+                );
+
+                tx_buf_el.set_msg_id(message.id);
+
+                if message.tx_event_fifo_control {
+                    tx_buf_el.set_tx_event_fifo_ctrl(message.tx_event_fifo_control);
+                    tx_buf_el.set_message_marker(buffer_id);
+                }
+
+                tx_buf_el.set_remote_transmit_req(message.remote_transmit_request);
+
+                if let FrameMode::FdLong | FrameMode::FdLongAndFast = message.frame_mode {
+                    tx_buf_el.set_err_state_indicator(message.error_state_indicator)
+                }
+
+                tx_buf_el.set_data_length(message.data_length_code);
+                if !message.remote_transmit_request {
+                    tx_buf_el.write_tx_buf_data(message.data_length_code, data.as_ptr());
+                }
+                tx_buf_el.set_frame_mode_req(message.frame_mode);
+
+                Ok(buffer_id)
+            }
+
+            fn get_tx_element_address(
+                &self,
+                ram_base_address: u32,
+                tx_buffers_start_address: u16,
+                buffer_number: TxBufferId,
+            ) -> Tx {
+                let num_of_config_bytes = 8u32;
+                let num_of_data_bytes = self.effects.get_tx_buffer_data_field_size() as u32;
+                let tx_buffer_size = num_of_config_bytes + num_of_data_bytes;
+                let tx_buffer_index = tx_buffer_size * u32::from(u8::from(buffer_number));
+
+                let tx_buffer_element_address =
+                    ram_base_address + u32::from(tx_buffers_start_address) + tx_buffer_index;
+
+                Tx::new(tx_buffer_element_address as *mut u8)
+            }
+
+            /// Current value of the timestamp counter (TSCV)
+            pub fn timestamp_counter(&self) -> u16 {
+                self.effects.get_timestamp_counter()
+            }
+
+            /// Reset the internal timestamp counter to zero
+            pub fn reset_timestamp_counter(&self) {
+                self.effects.reset_timestamp_counter();
+            }
+
+            /// Transmit and receive error counters (ECR). Reading them clears the error logging counter.
+            pub fn error_counters(&self) -> ErrorCounters {
+                ErrorCounters::from_register(self.effects.get_error_counter_register())
+            }
+
+            /// Protocol status (PSR). Reading it resets the last error codes to `NoChange`.
+            pub fn protocol_status(&self) -> ProtocolStatus {
+                ProtocolStatus::from_register(self.effects.get_protocol_status_register())
+            }
+
+            pub fn error_state(&self) -> ErrorState {
+                self.protocol_status().error_state()
+            }
+
+            /// Restart a node which is in the bus off state. The node takes part in the bus
+            /// activity again after 129 occurrences of 11 consecutive recessive bits.
+            /// Returns false if the node is not bus off.
+            pub fn restart_after_bus_off(&self) -> bool {
+                // When the node goes bus off, the hardware sets INIT to stop the bus activity
+                if !self.protocol_status().bus_off || !self.effects.is_initialization_enabled() {
+                    return false;
+                }
+
+                self.effects.clear_initialization();
+                true
+            }
+
+            /// Report the transitions of the warning, error passive and bus off states, signaled by
+            /// the EW, EP and BO interrupt flags, and clear the flags.
+            /// With [`BusOffRecovery::OnErrorHandler`] the node is restarted when it enters bus off.
+            /// This should be called from the interrupt handler of these interrupts, or periodically.
+            pub fn handle_error_state_changes(&self, mut on_change: impl FnMut(ErrorStateChange)) {
+                let flags = self.effects.get_interrupt_flags();
+                let has_flag = |interrupt: Interrupt| flags & (1 << interrupt as u32) != 0;
+
+                let warning = has_flag(Interrupt::WarningStatus);
+                let passive = has_flag(Interrupt::ErrorPassive);
+                let bus_off = has_flag(Interrupt::BusOffStatus);
+
+                if !(warning || passive || bus_off) {
+                    return;
+                }
+
+                let status = self.protocol_status();
+
+                if warning {
+                    self.effects.clear_interrupt_flag(Interrupt::WarningStatus);
+                    on_change(ErrorStateChange::Warning(status.warning));
+                }
+
+                if passive {
+                    self.effects.clear_interrupt_flag(Interrupt::ErrorPassive);
+                    on_change(ErrorStateChange::Passive(status.error_passive));
+                }
+
+                if bus_off {
+                    self.effects.clear_interrupt_flag(Interrupt::BusOffStatus);
+                    on_change(ErrorStateChange::BusOff(status.bus_off));
+
+                    if status.bus_off && self.bus_off_recovery == BusOffRecovery::OnErrorHandler {
+                        self.restart_after_bus_off();
+                    }
+                }
+            }
+
+            /// Read the oldest element of the Tx event FIFO and acknowledge it.
+            /// Frames are stored in the Tx event FIFO only if `TxConfig::event_fifo_size` is not zero.
+            pub fn read_tx_event(&self) -> Result<TxEvent, TxEventError> {
+                let tx_config = self.tx_config.ok_or(TxEventError::NotConfigured)?;
+
+                if !Self::is_tx_event_fifo_enabled(&tx_config) {
+                    return Err(TxEventError::NotConfigured);
+                }
+
+                if self.effects.is_tx_event_fifo_element_lost() {
+                    self.effects
+                        .clear_interrupt_flag(Interrupt::TxEventFifoEventLost);
+                    return Err(TxEventError::EventLost);
+                }
+
+                if self.effects.get_tx_event_fifo_fill_level() == 0 {
+                    return Err(TxEventError::FifoEmpty);
+                }
+
+                let index = self.effects.get_tx_event_fifo_get_index();
+
+                let element_address = self.ram_base_address
+                    + u32::from(tx_config.tx_event_fifo_start_address)
+                    + u32::from(index) * TX_EVENT_ELEMENT_SIZE;
+
+                let event = TxEventElement::new(element_address as *mut u8).read();
+
+                self.effects.set_tx_event_fifo_acknowledge_index(index);
+
+                Ok(event)
+            }
+
+            /// Number of elements stored in the Tx event FIFO
+            pub fn tx_event_fifo_fill_level(&self) -> u8 {
+                self.effects.get_tx_event_fifo_fill_level()
+            }
+
+            /// Request the cancellation of a pending transmission (TXBCR).
+            /// A transmission already in progress is not aborted.
+            pub fn cancel_tx_buffer(&self, tx_buffer_id: TxBufferId) {
+                let mask = 1u32 << u8::from(tx_buffer_id);
+                self.effects.set_tx_buffer_cancellation_request(mask);
+            }
+
+            /// Request the cancellation of all pending transmissions
+            pub fn cancel_all_tx_buffers(&self) {
+                let pending = self.effects.get_tx_buffer_request_pending();
+                self.effects.set_tx_buffer_cancellation_request(pending);
+            }
+
+            /// Cancellation finished (TXBCF), set when a cancellation request has been served
+            #[inline]
+            pub fn is_tx_buffer_cancellation_finished(&self, tx_buffer_id: TxBufferId) -> bool {
+                self.effects
+                    .is_tx_buffer_cancellation_finished(tx_buffer_id.into())
+            }
+
+            /// Transmission occurred (TXBTO), set when the buffer has been successfully transmitted
+            #[inline]
+            pub fn is_tx_buffer_transmission_occured(&self, tx_buffer_id: TxBufferId) -> bool {
+                self.effects
+                    .is_tx_buffer_transmission_occured(tx_buffer_id.into())
+            }
+
+            /// Transmission request pending (TXBRP)
+            #[inline]
+            pub fn is_tx_buffer_request_pending(&self, tx_buffer_id: TxBufferId) -> bool {
+                self.effects.is_tx_buffer_request_pending(tx_buffer_id)
+            }
+
+            /// Bitmap of the Tx buffers with a pending transmission request (TXBRP), bit n is buffer n
+            #[inline]
+            pub fn tx_buffers_request_pending(&self) -> u32 {
+                self.effects.get_tx_buffer_request_pending()
+            }
+        }
+
+        // Methods only valid on a node in power down mode
+        impl<I: NodeId> Node<$NodeReg, $ModuleReg, I, ClockStopped> {
+            /// Leave the power down mode and join the bus again, with the same configuration
+            #[must_use]
+            pub fn resume_clock(self) -> Node<$NodeReg, $ModuleReg, I, Configured> {
+                self.effects.clear_clock_stop_request();
+                self.effects.clear_initialization();
+                self.into_state()
+            }
+        }
+    };
+}
+
+impl_can_node!(
+    crate::pac::can0::Can0,
     crate::pac::can0::N,
     crate::can::Module0
 );
@@ -639,7 +1819,7 @@ pub enum RxFifoMode {
 }
 
 #[repr(u8)]
-#[derive(Clone, Copy, Default)]
+#[derive(Debug, Clone, Copy, Default, PartialEq)]
 pub enum DataFieldSize {
     #[default]
     _8,
@@ -653,6 +1833,20 @@ pub enum DataFieldSize {
 }
 
 impl DataFieldSize {
+    /// Number of data bytes of a buffer element
+    pub(crate) const fn to_bytes(self) -> u8 {
+        match self {
+            DataFieldSize::_8 => 8,
+            DataFieldSize::_12 => 12,
+            DataFieldSize::_16 => 16,
+            DataFieldSize::_20 => 20,
+            DataFieldSize::_24 => 24,
+            DataFieldSize::_32 => 32,
+            DataFieldSize::_48 => 48,
+            DataFieldSize::_64 => 64,
+        }
+    }
+
     fn to_esci_register_value(self) -> u8 {
         match self {
             DataFieldSize::_8 => 0,
@@ -700,6 +1894,37 @@ impl From<InterruptGroup> for u8 {
     }
 }
 
+/// Interrupt flags (IR) of a node
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct InterruptFlags(u32);
+
+impl InterruptFlags {
+    #[must_use]
+    pub fn contains(self, interrupt: Interrupt) -> bool {
+        self.0 & (1 << interrupt as u32) != 0
+    }
+
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    pub fn bits(self) -> u32 {
+        self.0
+    }
+
+    /// Set the message lost flag of the FIFO `from`, dedicated Rx buffers have no such flag
+    fn insert_message_lost(&mut self, from: ReadFrom) {
+        let interrupt = match from {
+            ReadFrom::RxFifo0 => Interrupt::RxFifo0messageLost,
+            ReadFrom::RxFifo1 => Interrupt::RxFifo1messageLost,
+            ReadFrom::Buffer(_) => return,
+        };
+        self.0 |= 1 << interrupt as u32;
+    }
+}
+
 #[derive(Debug, Clone, Copy)]
 pub enum Interrupt {
     RxFifo0newMessage,
@@ -784,275 +2009,7 @@ impl From<Tos> for u8 {
     }
 }
 
-#[derive(Clone, Copy)]
-pub struct InputMode(u32);
-impl InputMode {
-    pub const NO_PULL_DEVICE: Self = Self(0 << 3);
-    pub const PULL_DOWN: Self = Self(1 << 3);
-    pub const PULL_UP: Self = Self(2 << 3);
-}
-
-#[derive(Clone, Copy)]
-pub struct OutputMode(u32);
-impl OutputMode {
-    pub const PUSH_PULL: OutputMode = Self(0x10 << 3);
-    pub const OPEN_DRAIN: OutputMode = Self(0x18 << 3);
-    pub const NONE: OutputMode = Self(0);
-}
-
-#[derive(Clone, Copy)]
-pub enum PadDriver {
-    CmosAutomotiveSpeed1 = 0,
-    CmosAutomotiveSpeed2 = 1,
-    CmosAutomotiveSpeed3 = 2,
-    CmosAutomotiveSpeed4 = 3,
-    TtlSpeed1 = 8,
-    TtlSpeed2 = 9,
-    TtlSpeed3 = 10,
-    TtlSpeed4 = 11,
-    Ttl3v3speed1 = 12,
-    Ttl3v3speed2 = 13,
-    Ttl3v3speed3 = 14,
-    Ttl3v3speed4 = 15,
-}
-
-struct Port {
-    inner: crate::pac::p00::P00,
-}
-
-#[derive(Clone, Copy)]
-pub enum PortNumber {
-    _00,
-    _01,
-    _02,
-    _10,
-    _11,
-    _12,
-    _13,
-    _14,
-    _15,
-    _20,
-    _21,
-    _22,
-    _23,
-    _32,
-    _33,
-    _34,
-    _40,
-}
-
-#[derive(Debug, PartialEq)]
-enum State {
-    NotChanged = 0,
-    High = 1,
-    Low = 1 << 16,
-    Toggled = (1 << 16) | 1,
-}
-
-// TODO Is this needed? Can we get rid of it? Seems to be a duplicate of gpio
-impl Port {
-    fn new(port: PortNumber) -> Self {
-        use crate::pac::p00::P00;
-        use crate::pac::*;
-
-        let inner: P00 = match port {
-            PortNumber::_00 => P00,
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_01 => unsafe { transmute(P01) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_02 => unsafe { transmute(P02) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_10 => unsafe { transmute(P10) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_11 => unsafe { transmute(P11) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_12 => unsafe { transmute(P12) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_13 => unsafe { transmute(P13) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_14 => unsafe { transmute(P14) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_15 => unsafe { transmute(P15) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_20 => unsafe { transmute(P20) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_21 => unsafe { transmute(P21) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_22 => unsafe { transmute(P22) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_23 => unsafe { transmute(P23) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_32 => unsafe { transmute(P32) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_33 => unsafe { transmute(P33) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_34 => unsafe { transmute(P34) },
-            // SAFETY: The following transmutes are safe because the underlying registers have the same layout
-            PortNumber::_40 => unsafe { transmute(P40) },
-        };
-        Self { inner }
-    }
-
-    fn set_pin_state(&self, index: u8, action: State) {
-        // SAFETY: Each bit of OMR is W0, TODO: index should be in range [0, 32)?
-        unsafe {
-            self.inner.omr().init(|r| {
-                let v = (action as u32) << index;
-                r.set_raw(v)
-            })
-        };
-    }
-
-    fn toogle_pin(&self, index: u8) {
-        self.set_pin_state(index, State::Toggled)
-    }
-
-    fn set_pin_low(&self, index: u8) {
-        self.set_pin_state(index, State::Low)
-    }
-
-    fn set_pin_mode_output(&self, pin_index: u8, mode: OutputMode, index: OutputIdx) {
-        self.set_pin_mode(pin_index, (mode, index).into());
-    }
-
-    fn set_pin_mode_input(&self, pin_index: u8, mode: InputMode) {
-        self.set_pin_mode(pin_index, mode.into())
-    }
-
-    fn set_pin_mode(&self, index: u8, mode: Mode) {
-        // TODO: index should be in range [0, 16)
-        let ioc_index = index / 4;
-        let shift = (index & 0x3) * 8;
-
-        // TODO This unsafe code could be made safe by comparing the address (usize) of the port if only self.inner.0 was public
-        let is_supervisor =
-            // SAFETY: The following transmute is safe because the underlying registers have the same layout
-            unsafe { transmute::<_, usize>(self.inner) } == unsafe { transmute(crate::pac::P40) };
-
-        if is_supervisor {
-            // SAFETY: Bits 0:16 of PDISC are RW, TODO: index should be in range [0, 16)?
-            wdt_call::call_without_cpu_endinit(|| unsafe {
-                self.inner.pdisc().modify(|r| {
-                    let mut v = r.get_raw();
-                    v &= !(1 << index);
-                    r.set_raw(v)
-                })
-            });
-        }
-
-        // TODO Can we do this without transmute?
-        // TODO Use change_pin_mode_port_pin from gpio module instead?
-        let iocr: crate::pac::Reg<crate::pac::p00::Iocr0_SPEC, crate::pac::RW> = {
-            let iocr0 = self.inner.iocr0();
-            // SAFETY: The following transmute is safe, IOCR0 is a 32 bit register
-            let addr: *mut u32 = unsafe { transmute(iocr0) };
-            // SAFETY: The following operation is safe since ioc_index is in range [0, 4) TODO: see line 918
-            let addr = unsafe { addr.add(ioc_index as usize) };
-            // SAFETY: The following transmute is safe because IOCR0, IOCR4, IOCR8 and IOCR12 have the same layout
-            unsafe { transmute(addr) }
-        };
-
-        let v: u32 = (mode.0) << shift;
-        let m: u32 = 0xFFu32 << shift;
-
-        // SAFETY: Writing PCx (RW) for ioc_index
-        unsafe {
-            crate::intrinsics::load_modify_store(iocr.ptr(), v, m);
-        }
-    }
-
-    fn set_pin_pad_driver(&self, index: u8, driver: PadDriver) {
-        // TODO: index should be in range [0, 16)
-        let pdr_index = index / 8;
-        let shift = (index & 0x7) * 4;
-        let pdr: crate::pac::Reg<crate::pac::p00::Pdr0_SPEC, crate::pac::RW> = {
-            let pdr0 = self.inner.pdr0();
-            // SAFETY: The following transmute is safe, PDR0 is a 32 bit register
-            let addr: *mut u32 = unsafe { transmute(pdr0) };
-            // SAFETY: The following operation is safe since pdr_index is in range [0, 1] TODO: see line 957
-            let addr = unsafe { addr.add(pdr_index as usize) };
-            // SAFETY: The following transmute is safe because PDR0 and PDR1 have the same layout
-            unsafe { transmute(addr) }
-        };
-
-        wdt_call::call_without_cpu_endinit(|| {
-            let v: u32 = (driver as u32) << shift;
-            let m: u32 = 0xF << shift;
-            // SAFETY: Writing PDx and PLx (RW) for pdr_index
-            unsafe {
-                crate::intrinsics::load_modify_store(pdr.ptr(), v, m);
-            }
-        });
-    }
-}
-
-impl From<InputMode> for Mode {
-    fn from(value: InputMode) -> Self {
-        Mode(value.0)
-    }
-}
-
-impl From<(OutputMode, OutputIdx)> for Mode {
-    fn from(value: (OutputMode, OutputIdx)) -> Self {
-        Mode(value.0 .0 | value.1 .0)
-    }
-}
-
-struct Mode(u32);
-impl Mode {
-    const INPUT_NO_PULL_DEVICE: Mode = Self(0);
-    const INPUT_PULL_DOWN: Mode = Self(8);
-    const INPUT_PULL_UP: Mode = Self(0x10);
-    const OUTPUT_PUSH_PULL_GENERAL: Mode = Self(0x80);
-    const OUTPUT_PUSH_PULL_ALT1: Mode = Self(0x88);
-    const OUTPUT_PUSH_PULL_ALT2: Mode = Self(0x90);
-    const OUTPUT_PUSH_PULL_ALT3: Mode = Self(0x98);
-    const OUTPUT_PUSH_PULL_ALT4: Mode = Self(0xA0);
-    const OUTPUT_PUSH_PULL_ALT5: Mode = Self(0xA8);
-    const OUTPUT_PUSH_PULL_ALT6: Mode = Self(0xB0);
-    const OUTPUT_PUSH_PULL_ALT7: Mode = Self(0xB8);
-    const OUTPUT_OPEN_DRAIN_GENERAL: Mode = Self(0xC0);
-    const OUTPUT_OPEN_DRAIN_ALT1: Mode = Self(0xC8);
-    const OUTPUT_OPEN_DRAIN_ALT2: Mode = Self(0xD0);
-    const OUTPUT_OPEN_DRAIN_ALT3: Mode = Self(0xD8);
-    const OUTPUT_OPEN_DRAIN_ALT4: Mode = Self(0xE0);
-    const OUTPUT_OPEN_DRAIN_ALT5: Mode = Self(0xE8);
-    const OUTPUT_OPEN_DRAIN_ALT6: Mode = Self(0xF0);
-    const OUTPUT_OPEN_DRAIN_ALT7: Mode = Self(0xF8);
-}
-
-#[derive(Clone, Copy)]
-pub struct OutputIdx(u32);
-impl OutputIdx {
-    pub(crate) const GENERAL: Self = Self(0x10 << 3);
-    pub(crate) const ALT1: Self = Self(0x11 << 3);
-    pub(crate) const ALT2: Self = Self(0x12 << 3);
-    pub(crate) const ALT3: Self = Self(0x13 << 3);
-    pub(crate) const ALT4: Self = Self(0x14 << 3);
-    pub(crate) const ALT5: Self = Self(0x15 << 3);
-    pub(crate) const ALT6: Self = Self(0x16 << 3);
-    pub(crate) const ALT7: Self = Self(0x17 << 3);
-}
-
-#[derive(Clone, Copy)]
-pub struct RxdIn<M, N> {
-    port: PortNumber,
-    pin_index: u8,
-    select: RxSel,
-    _phantom: PhantomData<(M, N)>,
-}
-
-impl<M, N> RxdIn<M, N> {
-    pub(crate) const fn new(port: PortNumber, pin_index: u8, select: RxSel) -> Self {
-        Self {
-            port,
-            pin_index,
-            select,
-            _phantom: PhantomData,
-        }
-    }
-}
-
+/// Input of the node receive multiplexer (NPCR.RXSEL)
 #[derive(Clone, Copy)]
 pub enum RxSel {
     _A,
@@ -1080,25 +2037,6 @@ impl From<RxSel> for u8 {
     }
 }
 
-#[derive(Clone, Copy)]
-pub struct TxdOut<M: ModuleId, N: NodeId> {
-    port: PortNumber,
-    pin_index: u8,
-    select: OutputIdx,
-    _phantom: PhantomData<(M, N)>,
-}
-
-impl<M: ModuleId, N: NodeId> TxdOut<M, N> {
-    pub(crate) const fn new(port: PortNumber, pin_index: u8, select: OutputIdx) -> Self {
-        Self {
-            port,
-            pin_index,
-            select,
-            _phantom: PhantomData,
-        }
-    }
-}
-
 #[derive(Clone, Copy)]
 pub struct TxConfig {
     pub mode: TxMode,
@@ -1130,8 +2068,61 @@ pub struct RxConfig {
     pub rx_buffers_start_address: u16,
 }
 
-#[derive(Clone, Copy)]
-pub struct Pins<M: ModuleId, N: NodeId> {
-    pub tx: TxdOut<M, N>,
-    pub rx: RxdIn<M, N>,
+impl TxConfig {
+    /// Check that the used Tx buffers and Tx event FIFO fit in the sections of `layout` and
+    /// start at their addresses
+    fn fits_in(&self, layout: &NodeRamLayout) -> bool {
+        let reserved = &layout.requirements;
+        let buffers = match self.mode {
+            TxMode::DedicatedBuffers => self.dedicated_tx_buffers_number,
+            TxMode::Fifo | TxMode::Queue => self.fifo_queue_size,
+            TxMode::SharedFifo | TxMode::SharedQueue => self
+                .dedicated_tx_buffers_number
+                .saturating_add(self.fifo_queue_size),
+        };
+
+        let buffers_fit = buffers == 0
+            || (buffers <= reserved.tx_buffers
+                && self.buffer_data_field_size.to_bytes()
+                    <= reserved.tx_buffer_data_field_size.to_bytes()
+                && self.tx_buffers_start_address == layout.tx_buffers_start_address);
+        let event_fifo_fits = self.event_fifo_size == 0
+            || (self.event_fifo_size <= reserved.tx_event_fifo_size
+                && self.tx_event_fifo_start_address == layout.tx_event_fifo_start_address);
+
+        buffers_fit && event_fifo_fits
+    }
+}
+
+impl RxConfig {
+    /// Check that the used Rx FIFOs and Rx buffers fit in the sections of `layout` and start at
+    /// their addresses. The number of Rx buffers is checked by the filters storing into them.
+    fn fits_in(&self, layout: &NodeRamLayout) -> bool {
+        let reserved = &layout.requirements;
+        let (fifo0, fifo1, buffers) = match self.mode {
+            RxMode::DedicatedBuffers => (false, false, true),
+            RxMode::Fifo0 => (true, false, false),
+            RxMode::Fifo1 => (false, true, false),
+            RxMode::SharedFifo0 => (true, false, true),
+            RxMode::SharedFifo1 => (false, true, true),
+            RxMode::SharedAll => (true, true, true),
+        };
+
+        let fifo0_fits = !fifo0
+            || (self.fifo0_size <= reserved.rx_fifo0_size
+                && self.fifo0_data_field_size.to_bytes()
+                    <= reserved.rx_fifo0_data_field_size.to_bytes()
+                && self.rx_fifo0_start_address == layout.rx_fifo0_start_address);
+        let fifo1_fits = !fifo1
+            || (self.fifo1_size <= reserved.rx_fifo1_size
+                && self.fifo1_data_field_size.to_bytes()
+                    <= reserved.rx_fifo1_data_field_size.to_bytes()
+                && self.rx_fifo1_start_address == layout.rx_fifo1_start_address);
+        let buffers_fit = !buffers
+            || (self.buffer_data_field_size.to_bytes()
+                <= reserved.rx_buffer_data_field_size.to_bytes()
+                && self.rx_buffers_start_address == layout.rx_buffers_start_address);
+
+        fifo0_fits && fifo1_fits && buffers_fit
+    }
 }
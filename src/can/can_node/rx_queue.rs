@@ -0,0 +1,152 @@
+use crate::can::OwnedFrame;
+use core::cell::UnsafeCell;
+use core::mem::MaybeUninit;
+use core::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+
+/// Statically allocated, lock-free ring buffer of received frames.
+/// It is filled by [`Node::handle_rx_interrupt`](crate::can::Node) from the interrupt handler and
+/// emptied by the application with [`RxQueue::pop`]. One slot is always kept free, so the queue
+/// can hold `N - 1` frames. `N` must be at least 2, this is checked at compile time.
+///
+/// Pushing (or popping) from two contexts at the same time is not blocking: the context which
+/// comes second behaves as if the queue was full (or empty).
+pub struct RxQueue<const N: usize> {
+    slots: [UnsafeCell<MaybeUninit<OwnedFrame>>; N],
+    /// Next slot to be written, only modified by the producer
+    head: AtomicUsize,
+    /// Next slot to be read, only modified by the consumer
+    tail: AtomicUsize,
+    producing: AtomicBool,
+    consuming: AtomicBool,
+}
+
+// SAFETY: a slot is written only by the producer while it is not visible to the consumer, and
+// read only by the consumer while the producer cannot write it. The producing/consuming flags
+// guarantee there is at most one producer and one consumer at a time.
+unsafe impl<const N: usize> Sync for RxQueue<N> {}
+
+impl<const N: usize> RxQueue<N> {
+    // Only used as array initializer, each slot is a distinct cell
+    #[allow(clippy::declare_interior_mutable_const)]
+    const EMPTY_SLOT: UnsafeCell<MaybeUninit<OwnedFrame>> = UnsafeCell::new(MaybeUninit::uninit());
+
+    /// One slot is always free, a queue with less than two slots could not hold any frame
+    const VALID_SIZE: () = assert!(N > 1, "RxQueue needs at least two slots");
+
+    #[must_use]
+    pub const fn new() -> Self {
+        let () = Self::VALID_SIZE;
+
+        Self {
+            slots: [Self::EMPTY_SLOT; N],
+            head: AtomicUsize::new(0),
+            tail: AtomicUsize::new(0),
+            producing: AtomicBool::new(false),
+            consuming: AtomicBool::new(false),
+        }
+    }
+
+    /// Append a frame, gives it back if the queue is full
+    pub(crate) fn push(&self, frame: OwnedFrame) -> Result<(), OwnedFrame> {
+        if self.producing.swap(true, Ordering::Acquire) {
+            return Err(frame);
+        }
+
+        let head = self.head.load(Ordering::Relaxed);
+        let next = (head + 1) % N;
+
+        let result = if next == self.tail.load(Ordering::Acquire) {
+            Err(frame)
+        } else {
+            if let Some(slot) = self.slots.get(head) {
+                // SAFETY: the slot at head is not visible to the consumer until head is advanced
+                unsafe { (*slot.get()).write(frame) };
+            }
+            self.head.store(next, Ordering::Release);
+            Ok(())
+        };
+
+        self.producing.store(false, Ordering::Release);
+        result
+    }
+
+    /// Remove the oldest frame
+    pub fn pop(&self) -> Option<OwnedFrame> {
+        if self.consuming.swap(true, Ordering::Acquire) {
+            return None;
+        }
+
+        let tail = self.tail.load(Ordering::Relaxed);
+
+        let frame = if tail == self.head.load(Ordering::Acquire) {
+            None
+        } else {
+            let frame = self.slots.get(tail).map(|slot| {
+                // SAFETY: the producer does not write the slot at tail until tail is advanced
+                let slot = unsafe { &*slot.get() };
+                // SAFETY: slots between tail and head have been initialized by the producer
+                unsafe { slot.assume_init_read() }
+            });
+            self.tail.store((tail + 1) % N, Ordering::Release);
+            frame
+        };
+
+        self.consuming.store(false, Ordering::Release);
+        frame
+    }
+
+    /// Number of frames in the queue
+    pub fn len(&self) -> usize {
+        let head = self.head.load(Ordering::Acquire);
+        let tail = self.tail.load(Ordering::Acquire);
+        (head + N - tail) % N
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    pub(crate) fn is_full(&self) -> bool {
+        self.len() == N - 1
+    }
+}
+
+impl<const N: usize> Default for RxQueue<N> {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::RxQueue;
+    use crate::can::OwnedFrame;
+    use embedded_can::{Frame, StandardId};
+
+    fn frame(id: u16) -> OwnedFrame {
+        OwnedFrame::new(StandardId::new(id).unwrap(), &[1, 2]).unwrap()
+    }
+
+    #[test]
+    fn test_rx_queue() {
+        static QUEUE: RxQueue<4> = RxQueue::new();
+
+        assert!(QUEUE.is_empty());
+        assert_eq!(QUEUE.pop(), None);
+
+        assert!(QUEUE.push(frame(1)).is_ok());
+        assert!(QUEUE.push(frame(2)).is_ok());
+        assert!(QUEUE.push(frame(3)).is_ok());
+        assert!(QUEUE.is_full());
+        assert_eq!(QUEUE.push(frame(4)), Err(frame(4)));
+
+        assert_eq!(QUEUE.pop(), Some(frame(1)));
+        assert!(QUEUE.push(frame(4)).is_ok());
+        assert_eq!(QUEUE.len(), 3);
+
+        assert_eq!(QUEUE.pop(), Some(frame(2)));
+        assert_eq!(QUEUE.pop(), Some(frame(3)));
+        assert_eq!(QUEUE.pop(), Some(frame(4)));
+        assert_eq!(QUEUE.pop(), None);
+    }
+}
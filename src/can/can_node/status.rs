@@ -0,0 +1,257 @@
+#![allow(clippy::module_name_repetitions)]
+
+/// Error counters (ECR)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ErrorCounters {
+    /// Transmit error counter (TEC)
+    pub transmit: u8,
+    /// Receive error counter (REC)
+    pub receive: u8,
+    /// Receive error passive (RP), set when the receive error counter reached 128
+    pub receive_error_passive: bool,
+    /// CAN error logging (CEL), incremented on each error, cleared when read
+    pub error_logging: u8,
+}
+
+impl ErrorCounters {
+    pub(crate) fn from_register(ecr: u32) -> Self {
+        let [tec, rec_rp, cel, _] = ecr.to_le_bytes();
+        Self {
+            transmit: tec,
+            receive: rec_rp & 0x7F,
+            receive_error_passive: rec_rp & 0x80 != 0,
+            error_logging: cel,
+        }
+    }
+}
+
+/// Type of the last error occurred on the bus (LEC, DLEC)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LastErrorCode {
+    NoError,
+    StuffError,
+    FormError,
+    AckError,
+    Bit1Error,
+    Bit0Error,
+    CrcError,
+    /// No CAN bus event since the last read of the protocol status
+    NoChange,
+}
+
+impl From<u32> for LastErrorCode {
+    fn from(value: u32) -> Self {
+        match value & 0x7 {
+            0 => Self::NoError,
+            1 => Self::StuffError,
+            2 => Self::FormError,
+            3 => Self::AckError,
+            4 => Self::Bit1Error,
+            5 => Self::Bit0Error,
+            6 => Self::CrcError,
+            _ => Self::NoChange,
+        }
+    }
+}
+
+/// Communication state of the node (ACT)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Activity {
+    Synchronizing,
+    Idle,
+    Receiver,
+    Transmitter,
+}
+
+impl From<u32> for Activity {
+    fn from(value: u32) -> Self {
+        match value & 0x3 {
+            0 => Self::Synchronizing,
+            1 => Self::Idle,
+            2 => Self::Receiver,
+            _ => Self::Transmitter,
+        }
+    }
+}
+
+/// Fault confinement state of the node
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorState {
+    /// Both error counters are below the warning limit (96)
+    Active,
+    /// At least one error counter reached the warning limit (96)
+    Warning,
+    /// At least one error counter reached 128
+    Passive,
+    /// The transmit error counter reached 256, the node does not take part in the bus activity
+    BusOff,
+}
+
+/// Protocol status (PSR)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ProtocolStatus {
+    pub last_error_code: LastErrorCode,
+    pub data_last_error_code: LastErrorCode,
+    pub activity: Activity,
+    /// Error passive (EP)
+    pub error_passive: bool,
+    /// Warning status (EW)
+    pub warning: bool,
+    /// Bus off status (BO)
+    pub bus_off: bool,
+    /// Transceiver delay compensation value (TDCV), in minimum time quanta: the last measured
+    /// transceiver delay plus the TDC offset
+    pub transceiver_delay_compensation_value: u8,
+}
+
+impl ProtocolStatus {
+    pub(crate) fn from_register(psr: u32) -> Self {
+        let [_, _, tdcv, _] = psr.to_le_bytes();
+        Self {
+            last_error_code: LastErrorCode::from(psr),
+            data_last_error_code: LastErrorCode::from(psr >> 8),
+            activity: Activity::from(psr >> 3),
+            error_passive: psr & (1 << 5) != 0,
+            warning: psr & (1 << 6) != 0,
+            bus_off: psr & (1 << 7) != 0,
+            transceiver_delay_compensation_value: tdcv & 0x7F,
+        }
+    }
+
+    #[must_use]
+    pub fn error_state(self) -> ErrorState {
+        if self.bus_off {
+            ErrorState::BusOff
+        } else if self.error_passive {
+            ErrorState::Passive
+        } else if self.warning {
+            ErrorState::Warning
+        } else {
+            ErrorState::Active
+        }
+    }
+}
+
+/// Status of an Rx FIFO (RXF0S, RXF1S)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RxFifoStatus {
+    /// Number of messages stored in the FIFO (FnFL)
+    pub fill_level: u8,
+    /// Index of the oldest message (FnGI)
+    pub get_index: u8,
+    /// Index of the next message to be written (FnPI)
+    pub put_index: u8,
+    /// The FIFO is full (FnF)
+    pub full: bool,
+    /// A message has been discarded or overwritten because the FIFO was full (RFnL)
+    pub message_lost: bool,
+    /// The fill level reached the configured watermark level
+    pub watermark_reached: bool,
+}
+
+impl RxFifoStatus {
+    pub(crate) fn from_register(rxfs: u32, watermark_level: u8) -> Self {
+        let [fill_level, get_index, put_index, flags] = rxfs.to_le_bytes();
+        let fill_level = fill_level & 0x7F;
+        Self {
+            fill_level,
+            get_index: get_index & 0x3F,
+            put_index: put_index & 0x3F,
+            full: flags & 0x01 != 0,
+            message_lost: flags & 0x02 != 0,
+            watermark_reached: watermark_level != 0 && fill_level >= watermark_level,
+        }
+    }
+}
+
+/// Outcome of [`crate::can::Node::drain_fifo`]
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct DrainReport {
+    /// Number of frames passed to the callback
+    pub frames: usize,
+    /// At least one message has been lost: discarded by the hardware in blocking mode (RFnL),
+    /// or dropped and overwritten in overwrite mode
+    pub message_lost: bool,
+}
+
+/// What to do when the node enters the bus off state
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub enum BusOffRecovery {
+    /// The node stays in the bus off state until the application restarts it with
+    /// [`crate::can::Node::restart_after_bus_off`]
+    #[default]
+    Manual,
+    /// The node is restarted by [`crate::can::Node::handle_error_state_changes`] when it reports
+    /// the bus off state, then it joins the bus again after 129 occurrences of 11 consecutive
+    /// recessive bits. Nothing happens until that function is called: enable
+    /// [`crate::can::Interrupt::BusOffStatus`] and call it from the interrupt handler.
+    OnErrorHandler,
+}
+
+/// Transition of the fault confinement state, as reported by the EW, EP and BO interrupt flags
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ErrorStateChange {
+    /// Error warning status entered (true) or left (false)
+    Warning(bool),
+    /// Error passive state entered (true) or left (false)
+    Passive(bool),
+    /// Bus off state entered (true) or left (false)
+    BusOff(bool),
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_error_counters_decoding() {
+        let counters = ErrorCounters::from_register(0x0005_8A60);
+        assert_eq!(counters.transmit, 0x60);
+        assert_eq!(counters.receive, 0x0A);
+        assert!(counters.receive_error_passive);
+        assert_eq!(counters.error_logging, 5);
+    }
+
+    #[test]
+    fn test_protocol_status_decoding() {
+        // DLEC = no change, EW, ACT = receiver, LEC = ack error
+        let status = ProtocolStatus::from_register(0x0000_0753);
+        assert_eq!(status.last_error_code, LastErrorCode::AckError);
+        assert_eq!(status.data_last_error_code, LastErrorCode::NoChange);
+        assert_eq!(status.activity, Activity::Receiver);
+        assert!(status.warning);
+        assert!(!status.error_passive);
+        assert_eq!(status.error_state(), ErrorState::Warning);
+        assert_eq!(status.transceiver_delay_compensation_value, 0);
+
+        let status = ProtocolStatus::from_register(0x0023_0000);
+        assert_eq!(status.transceiver_delay_compensation_value, 0x23);
+
+        // Bus off takes precedence over the other states
+        let status = ProtocolStatus::from_register(0x0000_00E0);
+        assert_eq!(status.error_state(), ErrorState::BusOff);
+    }
+
+    #[test]
+    fn test_rx_fifo_status_decoding() {
+        // Full, message lost, put index 3, get index 3, fill level 8
+        let status = RxFifoStatus::from_register(0x0303_0308, 6);
+        assert_eq!(status.fill_level, 8);
+        assert_eq!(status.get_index, 3);
+        assert_eq!(status.put_index, 3);
+        assert!(status.full);
+        assert!(status.message_lost);
+        assert!(status.watermark_reached);
+
+        let status = RxFifoStatus::from_register(0x0002_0001, 6);
+        assert_eq!(status.fill_level, 1);
+        assert_eq!(status.put_index, 2);
+        assert!(!status.full);
+        assert!(!status.message_lost);
+        assert!(!status.watermark_reached);
+
+        // Watermark interrupt disabled
+        let status = RxFifoStatus::from_register(0x0000_0008, 0);
+        assert!(!status.watermark_reached);
+    }
+}
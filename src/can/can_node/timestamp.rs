@@ -0,0 +1,39 @@
+/// Extends the 16-bit timestamps of received frames and Tx events to a 64-bit monotonic time.
+/// A wrap around of the counter is detected when a timestamp is smaller than the previous one,
+/// so timestamps must be passed in order, and at least once per counter period.
+#[derive(Debug, Default, Clone, Copy)]
+pub struct TimestampExtender {
+    last: u16,
+    wraps: u64,
+}
+
+impl TimestampExtender {
+    #[must_use]
+    pub const fn new() -> Self {
+        Self { last: 0, wraps: 0 }
+    }
+
+    /// Convert a 16-bit timestamp to a 64-bit monotonic time, in timestamp counter ticks
+    pub fn extend(&mut self, timestamp: u16) -> u64 {
+        if timestamp < self.last {
+            self.wraps += 1;
+        }
+        self.last = timestamp;
+        (self.wraps << 16) | u64::from(timestamp)
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::TimestampExtender;
+
+    #[test]
+    fn test_timestamp_extender() {
+        let mut extender = TimestampExtender::new();
+        assert_eq!(extender.extend(0x0010), 0x0010);
+        assert_eq!(extender.extend(0xFFF0), 0xFFF0);
+        assert_eq!(extender.extend(0x0005), 0x1_0005);
+        assert_eq!(extender.extend(0x0005), 0x1_0005);
+        assert_eq!(extender.extend(0x0004), 0x2_0004);
+    }
+}
@@ -0,0 +1,123 @@
+use crate::can::{ModuleId, NodeId};
+use core::cell::UnsafeCell;
+use core::sync::atomic::{AtomicBool, Ordering};
+use core::task::Waker;
+
+/// Storage for the waker of a task waiting on a node, shared with the interrupt handler
+pub(crate) struct AtomicWaker {
+    waker: UnsafeCell<Option<Waker>>,
+    busy: AtomicBool,
+}
+
+// SAFETY: the waker is accessed only by the context which set the busy flag
+unsafe impl Sync for AtomicWaker {}
+
+impl AtomicWaker {
+    // Only used as array initializer, each element is a distinct cell
+    #[allow(clippy::declare_interior_mutable_const)]
+    const NEW: Self = Self::new();
+
+    pub(crate) const fn new() -> Self {
+        Self {
+            waker: UnsafeCell::new(None),
+            busy: AtomicBool::new(false),
+        }
+    }
+
+    /// Store the waker of the current task. The task must check its wake up condition after
+    /// registering, so a wake up happening during the registration is not lost.
+    pub(crate) fn register(&self, waker: &Waker) {
+        if self.busy.swap(true, Ordering::Acquire) {
+            // Being woken right now, make sure the task is polled again
+            waker.wake_by_ref();
+            return;
+        }
+
+        // SAFETY: the busy flag grants exclusive access to the waker
+        let stored = unsafe { &mut *self.waker.get() };
+        match stored {
+            Some(stored) if stored.will_wake(waker) => (),
+            _ => *stored = Some(waker.clone()),
+        }
+
+        self.busy.store(false, Ordering::Release);
+    }
+
+    /// Wake the stored task, if any
+    pub(crate) fn wake(&self) {
+        if self.busy.swap(true, Ordering::Acquire) {
+            // Being registered right now, the task checks its condition after registering
+            return;
+        }
+
+        // SAFETY: the busy flag grants exclusive access to the waker
+        let waker = unsafe { &mut *self.waker.get() }.take();
+
+        self.busy.store(false, Ordering::Release);
+
+        if let Some(waker) = waker {
+            waker.wake();
+        }
+    }
+}
+
+/// One waker per node of each module
+const WAKERS_COUNT: usize = 8;
+
+pub(crate) static TX_WAKERS: [AtomicWaker; WAKERS_COUNT] = [AtomicWaker::NEW; WAKERS_COUNT];
+pub(crate) static RX_WAKERS: [AtomicWaker; WAKERS_COUNT] = [AtomicWaker::NEW; WAKERS_COUNT];
+
+pub(crate) fn waker_index<M: ModuleId, I: NodeId>() -> usize {
+    M::INDEX * 4 + I::INDEX
+}
+
+/// Wake the tasks waiting in `transmit_async` and `receive_async` of a node.
+/// Call it from the interrupt handler of the service request line the node interrupts are routed
+/// to (see `Node::setup_interrupt`), after clearing the interrupt flags: the futures do not clear
+/// them. The futures check the node state when polled, so spurious wake ups are harmless.
+pub fn wake_node<M: ModuleId, I: NodeId>(_module: M, _node: I) {
+    let index = waker_index::<M, I>();
+
+    if let Some(waker) = TX_WAKERS.get(index) {
+        waker.wake();
+    }
+    if let Some(waker) = RX_WAKERS.get(index) {
+        waker.wake();
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::AtomicWaker;
+    use std::sync::atomic::{AtomicUsize, Ordering};
+    use std::sync::Arc;
+    use std::task::{Wake, Waker};
+
+    struct CountingWaker(AtomicUsize);
+
+    impl Wake for CountingWaker {
+        fn wake(self: Arc<Self>) {
+            self.0.fetch_add(1, Ordering::Relaxed);
+        }
+    }
+
+    #[test]
+    fn test_atomic_waker() {
+        let counter = Arc::new(CountingWaker(AtomicUsize::new(0)));
+        let waker = Waker::from(counter.clone());
+        let atomic_waker = AtomicWaker::new();
+
+        // Nothing registered
+        atomic_waker.wake();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 0);
+
+        atomic_waker.register(&waker);
+        atomic_waker.register(&waker);
+        atomic_waker.wake();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+
+        // The waker is consumed by wake
+        atomic_waker.wake();
+        assert_eq!(counter.0.load(Ordering::Relaxed), 1);
+    }
+}
@@ -1,6 +1,6 @@
 #![allow(unused_variables)]
 
-use crate::can::msg::MessageId;
+use crate::can::msg::{FrameMode, MessageId};
 
 // TODO This should be DataLength(u8) and only from_length and to_length should be public
 /// Data length code
@@ -123,9 +123,165 @@ impl<'a> Frame<'a> {
     }
 }
 
+/// An owned CAN frame, with inline storage for up to 64 data bytes.
+/// It can be used both to transmit and to receive frames.
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub struct OwnedFrame {
+    pub(crate) id: embedded_can::Id,
+    pub(crate) remote: bool,
+    pub(crate) frame_mode: FrameMode,
+    pub(crate) error_state_indicator: bool,
+    pub(crate) timestamp: Option<u16>,
+    pub(crate) filter_index: Option<u8>,
+    pub(crate) len: u8,
+    pub(crate) data: [u8; 64],
+}
+
+impl OwnedFrame {
+    /// Create a CAN FD data frame, with or without bit rate switching
+    pub fn new_fd(
+        id: impl Into<embedded_can::Id>,
+        data: &[u8],
+        bit_rate_switch: bool,
+    ) -> Option<Self> {
+        let frame = <Self as embedded_can::Frame>::new(id, data)?;
+        let frame_mode = if bit_rate_switch {
+            FrameMode::FdLongAndFast
+        } else {
+            FrameMode::FdLong
+        };
+        Some(Self {
+            frame_mode,
+            ..frame
+        })
+    }
+
+    /// Set the error state indicator flag (CAN FD frames only)
+    #[must_use]
+    pub fn with_error_state_indicator(self, error_state_indicator: bool) -> Self {
+        Self {
+            error_state_indicator,
+            ..self
+        }
+    }
+
+    #[must_use]
+    pub fn frame_mode(&self) -> FrameMode {
+        self.frame_mode
+    }
+
+    /// FD Format (FDF) flag
+    #[must_use]
+    pub fn is_fd(&self) -> bool {
+        self.frame_mode != FrameMode::Standard
+    }
+
+    /// Bit Rate Switch (BRS) flag
+    #[must_use]
+    pub fn bit_rate_switch(&self) -> bool {
+        self.frame_mode == FrameMode::FdLongAndFast
+    }
+
+    /// Error State Indicator (ESI) flag
+    #[must_use]
+    pub fn error_state_indicator(&self) -> bool {
+        self.error_state_indicator
+    }
+
+    /// Rx timestamp, only available on received frames
+    #[must_use]
+    pub fn timestamp(&self) -> Option<u16> {
+        self.timestamp
+    }
+
+    /// Index of the filter element which accepted the frame, only available on received frames.
+    /// `None` if the frame has been accepted as non-matching frame.
+    #[must_use]
+    pub fn filter_index(&self) -> Option<u8> {
+        self.filter_index
+    }
+
+    pub(crate) fn message_id(&self) -> MessageId {
+        self.id.into()
+    }
+
+    pub(crate) fn data_length_code(&self) -> Option<DataLenghtCode> {
+        DataLenghtCode::from_length(usize::from(self.len))
+    }
+}
+
+impl embedded_can::Frame for OwnedFrame {
+    fn new(id: impl Into<embedded_can::Id>, data: &[u8]) -> Option<Self> {
+        DataLenghtCode::from_length(data.len())?;
+
+        let mut buffer = [0u8; 64];
+        buffer.get_mut(..data.len())?.copy_from_slice(data);
+
+        // Data longer than 8 bytes can only be carried by CAN FD frames
+        let frame_mode = if data.len() > 8 {
+            FrameMode::FdLong
+        } else {
+            FrameMode::Standard
+        };
+
+        Some(Self {
+            id: id.into(),
+            remote: false,
+            frame_mode,
+            error_state_indicator: false,
+            timestamp: None,
+            filter_index: None,
+            len: u8::try_from(data.len()).ok()?,
+            data: buffer,
+        })
+    }
+
+    fn new_remote(id: impl Into<embedded_can::Id>, dlc: usize) -> Option<Self> {
+        if dlc > 8 {
+            return None;
+        }
+
+        Some(Self {
+            id: id.into(),
+            remote: true,
+            frame_mode: FrameMode::Standard,
+            error_state_indicator: false,
+            timestamp: None,
+            filter_index: None,
+            len: u8::try_from(dlc).ok()?,
+            data: [0u8; 64],
+        })
+    }
+
+    fn is_extended(&self) -> bool {
+        matches!(self.id, embedded_can::Id::Extended(_))
+    }
+
+    fn is_remote_frame(&self) -> bool {
+        self.remote
+    }
+
+    fn id(&self) -> embedded_can::Id {
+        self.id
+    }
+
+    fn dlc(&self) -> usize {
+        usize::from(self.len)
+    }
+
+    fn data(&self) -> &[u8] {
+        if self.remote {
+            return &[];
+        }
+        self.data.get(..usize::from(self.len)).unwrap_or(&[])
+    }
+}
+
 #[cfg(test)]
 mod test {
-    use super::DataLenghtCode;
+    use super::{DataLenghtCode, OwnedFrame};
+    use crate::can::msg::FrameMode;
+    use embedded_can::{ExtendedId, Frame, StandardId};
 
     #[test]
     fn test_data_length_code() {
@@ -156,4 +312,49 @@ mod test {
             test(DataLenghtCode::try_from(i).unwrap());
         }
     }
+
+    #[test]
+    fn test_owned_frame() {
+        let id = StandardId::new(0x123).unwrap();
+
+        let frame = OwnedFrame::new(id, &[1, 2, 3]).unwrap();
+        assert!(frame.is_standard());
+        assert!(frame.is_data_frame());
+        assert_eq!(frame.dlc(), 3);
+        assert_eq!(frame.data(), &[1, 2, 3]);
+
+        // 9 bytes is not a valid CAN FD data length
+        assert!(OwnedFrame::new(id, &[0; 9]).is_none());
+        assert_eq!(OwnedFrame::new(id, &[0xAA; 64]).unwrap().dlc(), 64);
+
+        let id = ExtendedId::new(0x18FE_F100).unwrap();
+        let frame = OwnedFrame::new_remote(id, 8).unwrap();
+        assert!(frame.is_extended());
+        assert!(frame.is_remote_frame());
+        assert_eq!(frame.dlc(), 8);
+        assert!(frame.data().is_empty());
+        assert!(OwnedFrame::new_remote(id, 9).is_none());
+    }
+
+    #[test]
+    fn test_owned_frame_fd_flags() {
+        let id = StandardId::new(0x7DF).unwrap();
+
+        let frame = OwnedFrame::new(id, &[0; 8]).unwrap();
+        assert_eq!(frame.frame_mode(), FrameMode::Standard);
+
+        // More than 8 bytes implies a CAN FD frame
+        let frame = OwnedFrame::new(id, &[0; 12]).unwrap();
+        assert!(frame.is_fd());
+        assert!(!frame.bit_rate_switch());
+
+        let frame = OwnedFrame::new_fd(id, &[0; 2], true)
+            .unwrap()
+            .with_error_state_indicator(true);
+        assert_eq!(frame.frame_mode(), FrameMode::FdLongAndFast);
+        assert!(frame.bit_rate_switch());
+        assert!(frame.error_state_indicator());
+        assert_eq!(frame.timestamp(), None);
+        assert_eq!(frame.filter_index(), None);
+    }
 }
@@ -0,0 +1,233 @@
+// TODO Remove this once the code is stable
+#![allow(clippy::undocumented_unsafe_blocks)]
+#![allow(clippy::cast_possible_truncation)]
+
+use crate::can::msg::{Filter, FilterElementConfiguration, FilterType};
+use crate::can::reg;
+use core::mem::transmute;
+
+pub(crate) struct StdFilter {
+    inner: reg::msg_filter::StdFilterMsg,
+}
+
+impl StdFilter {
+    pub(crate) fn new(ptr: *mut u8) -> Self {
+        Self {
+            inner: unsafe { transmute(ptr) },
+        }
+    }
+}
+
+impl StdFilter {
+    pub(crate) fn set(&self, filter: &Filter) {
+        let sfec = filter_element_configuration_value(filter.element_configuration);
+        let sft = standard_filter_type_value(filter.typ);
+
+        let (sfid1, sfid2) =
+            if filter.element_configuration == FilterElementConfiguration::StoreInRxBuffer {
+                // SFID2[10:9] = 0 means "store into Rx buffer", SFID2[5:0] is the buffer offset
+                (filter.id1 as u16, u16::from(filter.rx_buffer_offset))
+            } else {
+                (filter.id1 as u16, filter.id2 as u16)
+            };
+
+        // SAFETY: the whole S0 word is written, every field value is masked by RegisterField::set
+        unsafe {
+            self.inner.s0().init(|r| {
+                r.sft()
+                    .set(sft)
+                    .sfec()
+                    .set(sfec)
+                    .sfid1()
+                    .set(sfid1)
+                    .sfid2()
+                    .set(sfid2)
+            })
+        };
+    }
+
+    pub(crate) fn disable(&self) {
+        // SAFETY: the whole S0 word is written, SFEC = 0 disables the filter element
+        unsafe {
+            self.inner
+                .s0()
+                .init(|r| r.sft().set(standard_filter_type_value(FilterType::None)))
+        };
+    }
+}
+
+pub(crate) struct ExtFilter {
+    inner: reg::msg_filter::ExtFilterMsg,
+}
+
+impl ExtFilter {
+    pub(crate) fn new(ptr: *mut u8) -> Self {
+        Self {
+            inner: unsafe { transmute(ptr) },
+        }
+    }
+}
+
+impl ExtFilter {
+    pub(crate) fn set(&self, filter: &Filter) {
+        // There is no "disabled" filter type for extended filter elements (EFT = 3 is a range
+        // filter without XIDAM masking), so the whole element is disabled instead
+        let Some(eft) = extended_filter_type_value(filter.typ) else {
+            self.disable();
+            return;
+        };
+        let efec = filter_element_configuration_value(filter.element_configuration);
+
+        let efid2 = if filter.element_configuration == FilterElementConfiguration::StoreInRxBuffer {
+            // EFID2[10:9] = 0 means "store into Rx buffer", EFID2[5:0] is the buffer offset
+            u32::from(filter.rx_buffer_offset)
+        } else {
+            filter.id2
+        };
+
+        // SAFETY: the whole F1 word is written, every field value is masked by RegisterField::set
+        unsafe {
+            self.inner
+                .f1()
+                .init(|r| r.eft().set(eft).efid2().set(efid2))
+        };
+
+        // F0 is written last, so the element is enabled only when it is complete
+        // SAFETY: the whole F0 word is written, every field value is masked by RegisterField::set
+        unsafe {
+            self.inner
+                .f0()
+                .init(|r| r.efec().set(efec).efid1().set(filter.id1))
+        };
+    }
+
+    pub(crate) fn disable(&self) {
+        // SAFETY: the whole F0 word is written, EFEC = 0 disables the filter element
+        unsafe { self.inner.f0().init(|r| r) };
+        // SAFETY: the whole F1 word is written
+        unsafe { self.inner.f1().init(|r| r) };
+    }
+}
+
+fn filter_element_configuration_value(configuration: FilterElementConfiguration) -> u8 {
+    match configuration {
+        FilterElementConfiguration::Disable => 0,
+        FilterElementConfiguration::StoreInRxFifo0 => 1,
+        FilterElementConfiguration::StoreInRxFifo1 => 2,
+        FilterElementConfiguration::RejectId => 3,
+        FilterElementConfiguration::SetPriority => 4,
+        FilterElementConfiguration::SetPriorityAndStoreInFifo0 => 5,
+        FilterElementConfiguration::SetPriorityAndStoreInFifo1 => 6,
+        FilterElementConfiguration::StoreInRxBuffer => 7,
+    }
+}
+
+fn standard_filter_type_value(typ: FilterType) -> u8 {
+    match typ {
+        // Standard IDs are never masked, both range types are the same
+        FilterType::Range | FilterType::RangeWithoutMask => 0,
+        FilterType::Dualid => 1,
+        FilterType::Classic => 2,
+        FilterType::None => 3,
+    }
+}
+
+fn extended_filter_type_value(typ: FilterType) -> Option<u8> {
+    match typ {
+        // Range filter with XIDAM mask applied
+        FilterType::Range => Some(0),
+        FilterType::Dualid => Some(1),
+        FilterType::Classic => Some(2),
+        FilterType::RangeWithoutMask => Some(3),
+        FilterType::None => None,
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::can::msg::RxBufferId;
+
+    fn filter(
+        element_configuration: FilterElementConfiguration,
+        typ: FilterType,
+        id1: u32,
+        id2: u32,
+    ) -> Filter {
+        Filter {
+            number: 0,
+            element_configuration,
+            typ,
+            id1,
+            id2,
+            rx_buffer_offset: RxBufferId::new(5).unwrap(),
+        }
+    }
+
+    #[test]
+    fn test_std_filter_encoding() {
+        let mut element = 0u32;
+        let std_filter = StdFilter::new(core::ptr::addr_of_mut!(element).cast());
+
+        std_filter.set(&filter(
+            FilterElementConfiguration::StoreInRxFifo1,
+            FilterType::Classic,
+            0x123,
+            0x7F0,
+        ));
+        assert_eq!(element, (2 << 30) | (2 << 27) | (0x123 << 16) | 0x7F0);
+
+        std_filter.set(&filter(
+            FilterElementConfiguration::StoreInRxBuffer,
+            FilterType::Classic,
+            0x456,
+            0x7FF,
+        ));
+        assert_eq!(element, (2 << 30) | (7 << 27) | (0x456 << 16) | 5);
+
+        std_filter.disable();
+        assert_eq!(element, 3 << 30);
+    }
+
+    #[test]
+    fn test_ext_filter_encoding() {
+        let mut element = [0u32; 2];
+        let ext_filter = ExtFilter::new(element.as_mut_ptr().cast());
+
+        ext_filter.set(&filter(
+            FilterElementConfiguration::StoreInRxFifo0,
+            FilterType::Range,
+            0x18FE_F100,
+            0x18FE_F1FF,
+        ));
+        assert_eq!(element, [(1 << 29) | 0x18FE_F100, 0x18FE_F1FF]);
+
+        ext_filter.set(&filter(
+            FilterElementConfiguration::StoreInRxBuffer,
+            FilterType::Classic,
+            0x1234_5678,
+            0,
+        ));
+        assert_eq!(element, [(7 << 29) | 0x1234_5678, (2 << 30) | 5]);
+
+        ext_filter.set(&filter(
+            FilterElementConfiguration::StoreInRxFifo1,
+            FilterType::RangeWithoutMask,
+            0x100,
+            0x1FF,
+        ));
+        assert_eq!(element, [(2 << 29) | 0x100, (3 << 30) | 0x1FF]);
+
+        // A filter of type None must not become an active range filter
+        ext_filter.set(&filter(
+            FilterElementConfiguration::StoreInRxFifo0,
+            FilterType::None,
+            0x100,
+            0x1FF,
+        ));
+        assert_eq!(element, [0, 0]);
+
+        ext_filter.disable();
+        assert_eq!(element, [0, 0]);
+    }
+}
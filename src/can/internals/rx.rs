@@ -1,8 +1,8 @@
 // TODO Remove this once the code is stable
 #![allow(clippy::undocumented_unsafe_blocks)]
 
-use crate::can::msg::MessageIdLength;
-use crate::can::{frame::DataLenghtCode, reg, FrameMode};
+use crate::can::msg::{MessageId, MessageIdLength};
+use crate::can::{frame::DataLenghtCode, reg, FrameMode, OwnedFrame};
 use crate::log::debug;
 use core::mem::transmute;
 
@@ -10,12 +10,15 @@ use core::mem::transmute;
 
 pub(crate) struct Rx {
     inner: reg::msg_rx::RxMsg,
+    /// Size in bytes of the data field of the element (Rx FIFO or dedicated buffer setting)
+    data_field_size: usize,
 }
 
 impl Rx {
-    pub(crate) fn new(ptr: *mut u8) -> Self {
+    pub(crate) fn new(ptr: *mut u8, data_field_size: u8) -> Self {
         Self {
             inner: unsafe { transmute(ptr) },
+            data_field_size: usize::from(data_field_size),
         }
     }
 }
@@ -53,11 +56,24 @@ impl Rx {
     #[inline]
     pub(crate) fn get_data_length(&self) -> DataLenghtCode {
         // SAFETY: each bit of R1 is RWH
-        let d = unsafe { self.inner.r1().read() }.dlc().get();
+        let r1 = unsafe { self.inner.r1().read() };
+        let d = r1.dlc().get();
+        // Classic CAN frames carry at most 8 data bytes, DLC values 9 to 15 also mean 8 bytes
+        let d = if r1.fdf().get() { d } else { d.min(8) };
         // SAFETY: d is a valid DataLenghtCode, because it is a 4 bit field
         unsafe { DataLenghtCode::try_from(d).unwrap_unchecked() }
     }
 
+    /// Number of data bytes stored in the element: zero for remote frames, and at most the data
+    /// field size, because the bytes of a frame which does not fit into the element are dropped
+    pub(crate) fn get_stored_data_length(&self) -> usize {
+        if self.is_remote_frame() {
+            0
+        } else {
+            self.get_data_length().to_length().min(self.data_field_size)
+        }
+    }
+
     pub(crate) fn get_frame_mode(&self) -> FrameMode {
         // SAFETY: each bit of R1 is RWH
         let r1 = unsafe { self.inner.r1().read() };
@@ -73,12 +89,140 @@ impl Rx {
         }
     }
 
-    pub(crate) fn read_data(&self, data_length_code: DataLenghtCode, data: *mut u8) {
+    /// Copy the first `data.len()` bytes of the data field. Bytes past the data field size are
+    /// not read, so the next element of the message RAM is never accessed.
+    pub(crate) fn read_data(&self, data: &mut [u8]) {
         let source_address = self.inner.db().ptr() as *const u8;
-        let length = data_length_code.to_length();
+        let length = data.len().min(self.data_field_size);
 
         debug!("reading {} bytes from {:x}", length, source_address);
 
-        unsafe { core::ptr::copy_nonoverlapping(source_address, data, length) };
+        unsafe { reg::read_message_ram(source_address, data.as_mut_ptr(), length) };
+    }
+
+    #[inline]
+    pub(crate) fn is_remote_frame(&self) -> bool {
+        // SAFETY: each bit of R0 is RWH
+        unsafe { self.inner.r0().read() }.rtr().get()
+    }
+
+    #[inline]
+    pub(crate) fn get_error_state_indicator(&self) -> bool {
+        // SAFETY: each bit of R0 is RWH
+        unsafe { self.inner.r0().read() }.esi().get()
+    }
+
+    #[inline]
+    pub(crate) fn get_timestamp(&self) -> u16 {
+        // SAFETY: each bit of R1 is RWH
+        unsafe { self.inner.r1().read() }.rxts().get()
+    }
+
+    /// Index of the matching filter element, None if the frame is a non-matching frame
+    #[inline]
+    pub(crate) fn get_filter_index(&self) -> Option<u8> {
+        // SAFETY: each bit of R1 is RWH
+        let r1 = unsafe { self.inner.r1().read() };
+        if r1.anmf().get() {
+            None
+        } else {
+            Some(r1.fidx().get())
+        }
+    }
+
+    pub(crate) fn read_frame(&self) -> OwnedFrame {
+        let id = MessageId {
+            data: self.get_message_id(),
+            length: self.get_message_id_length(),
+        };
+        let remote = self.is_remote_frame();
+        // The DLC of a remote frame is the requested length, no data is stored
+        let len = if remote {
+            self.get_data_length().to_length()
+        } else {
+            self.get_stored_data_length()
+        };
+
+        let mut frame = OwnedFrame {
+            // The id read from the message RAM is always in range, so the conversion cannot fail
+            id: embedded_can::Id::try_from(id)
+                .unwrap_or(embedded_can::Id::Standard(embedded_can::StandardId::ZERO)),
+            remote,
+            frame_mode: self.get_frame_mode(),
+            error_state_indicator: self.get_error_state_indicator(),
+            timestamp: Some(self.get_timestamp()),
+            filter_index: self.get_filter_index(),
+            // Data length is at most 64 bytes
+            len: u8::try_from(len).unwrap_or(64),
+            data: [0u8; 64],
+        };
+
+        if !remote {
+            if let Some(data) = frame.data.get_mut(..len) {
+                self.read_data(data);
+            }
+        }
+
+        frame
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::Rx;
+    use crate::can::FrameMode;
+    use embedded_can::Frame;
+
+    /// Element with an 8 bytes data field, followed by the header of the next element
+    fn element(r0: u32, r1: u32) -> [u32; 6] {
+        [r0, r1, 0x0403_0201, 0x0807_0605, 0xAAAA_AAAA, 0xBBBB_BBBB]
+    }
+
+    #[test]
+    fn test_classic_frame_with_dlc_above_8() {
+        // Standard ID 0x123, DLC 15, FDF = 0
+        let mut element = element(0x123 << 18, 15 << 16);
+        let rx = Rx::new(element.as_mut_ptr().cast(), 8);
+
+        assert_eq!(rx.get_data_length().to_length(), 8);
+        assert_eq!(rx.get_stored_data_length(), 8);
+
+        let frame = rx.read_frame();
+        assert_eq!(frame.frame_mode(), FrameMode::Standard);
+        assert_eq!(frame.dlc(), 8);
+        assert_eq!(frame.data(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_fd_frame_larger_than_element() {
+        // Standard ID 0x123, DLC 15 (64 bytes), FDF = 1, received into an 8 bytes element
+        let mut element = element(0x123 << 18, (1 << 21) | (15 << 16));
+        let rx = Rx::new(element.as_mut_ptr().cast(), 8);
+
+        assert_eq!(rx.get_data_length().to_length(), 64);
+        assert_eq!(rx.get_stored_data_length(), 8);
+
+        let mut data = [0u8; 64];
+        rx.read_data(&mut data);
+        assert_eq!(data.get(8..16), Some(&[0u8; 8][..]));
+
+        let frame = rx.read_frame();
+        assert_eq!(frame.frame_mode(), FrameMode::FdLong);
+        assert_eq!(frame.dlc(), 8);
+        assert_eq!(frame.data(), &[1, 2, 3, 4, 5, 6, 7, 8]);
+    }
+
+    #[test]
+    fn test_remote_frame() {
+        // Standard ID 0x123, RTR = 1, DLC 4, the data field holds a previous frame
+        let mut element = element((1 << 29) | (0x123 << 18), 4 << 16);
+        let rx = Rx::new(element.as_mut_ptr().cast(), 8);
+
+        assert!(rx.is_remote_frame());
+
+        let frame = rx.read_frame();
+        assert!(frame.is_remote_frame());
+        assert_eq!(frame.dlc(), 4);
+        assert!(frame.data().is_empty());
     }
 }
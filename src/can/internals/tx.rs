@@ -84,6 +84,57 @@ impl Tx {
         let destination_address = self.inner.db().ptr() as *mut u8;
         let length = data_length_code.to_length();
 
-        unsafe { core::ptr::copy_nonoverlapping(data, destination_address, length) };
+        unsafe { reg::write_message_ram(data, destination_address, length) };
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_remote_request_element() {
+        // Data field of the element is left untouched by a remote frame
+        let mut element = [0u32, 0, 0xAAAA_AAAA, 0xBBBB_BBBB];
+        let tx = Tx::new(element.as_mut_ptr().cast());
+
+        tx.set_msg_id(MessageId {
+            data: 0x123,
+            length: MessageIdLength::Standard,
+        });
+        tx.set_remote_transmit_req(true);
+        tx.set_data_length(DataLenghtCode::_4);
+        tx.set_frame_mode_req(FrameMode::Standard);
+
+        assert_eq!(
+            element,
+            [(1 << 29) | (0x123 << 18), 4 << 16, 0xAAAA_AAAA, 0xBBBB_BBBB]
+        );
+    }
+
+    #[test]
+    fn test_remote_answer_element() {
+        // Extended remote frame previously stored in the element, replaced by its answer
+        let mut element = [(0b011 << 29) | 0x1234_5678, 8 << 16, 0, 0];
+        let tx = Tx::new(element.as_mut_ptr().cast());
+
+        tx.set_msg_id(MessageId {
+            data: 0x1234_5678,
+            length: MessageIdLength::Extended,
+        });
+        tx.set_remote_transmit_req(false);
+        tx.set_data_length(DataLenghtCode::_2);
+        tx.write_tx_buf_data(DataLenghtCode::_2, [0x11u8, 0x22].as_ptr());
+        tx.set_frame_mode_req(FrameMode::Standard);
+
+        assert_eq!(
+            element,
+            [
+                (1 << 30) | 0x1234_5678,
+                2 << 16,
+                u32::from_le_bytes([0x11, 0x22, 0, 0]),
+                0
+            ]
+        );
     }
 }
@@ -0,0 +1,114 @@
+// TODO Remove this once the code is stable
+#![allow(clippy::undocumented_unsafe_blocks)]
+
+use crate::can::msg::{MessageId, MessageIdLength, TxEvent, TxEventType};
+use crate::can::{frame::DataLenghtCode, reg, FrameMode};
+use core::mem::transmute;
+
+pub(crate) struct TxEventElement {
+    inner: reg::msg_tx_event::TxEventMsg,
+}
+
+impl TxEventElement {
+    pub(crate) fn new(ptr: *mut u8) -> Self {
+        Self {
+            inner: unsafe { transmute(ptr) },
+        }
+    }
+}
+
+impl TxEventElement {
+    pub(crate) fn read(&self) -> TxEvent {
+        // SAFETY: E0 is read only, the element is not modified by hardware until it is acknowledged
+        let e0 = unsafe { self.inner.e0().read() };
+        // SAFETY: E1 is read only, the element is not modified by hardware until it is acknowledged
+        let e1 = unsafe { self.inner.e1().read() };
+
+        let id = if e0.xtd().get() {
+            MessageId {
+                data: e0.id().get(),
+                length: MessageIdLength::Extended,
+            }
+        } else {
+            // Standard ids are stored in bits 28:18
+            MessageId {
+                data: e0.id().get() >> 18,
+                length: MessageIdLength::Standard,
+            }
+        };
+
+        let frame_mode = match (e1.fdf().get(), e1.brs().get()) {
+            (false, _) => FrameMode::Standard,
+            (true, false) => FrameMode::FdLong,
+            (true, true) => FrameMode::FdLongAndFast,
+        };
+
+        let event_type = match e1.et().get() {
+            0b01 => TxEventType::Transmitted,
+            0b10 => TxEventType::TransmittedInSpiteOfCancellation,
+            _ => TxEventType::Reserved,
+        };
+
+        TxEvent {
+            id,
+            // SAFETY: DLC is a 4 bit field, every value is a valid DataLenghtCode
+            data_length_code: unsafe {
+                DataLenghtCode::try_from(e1.dlc().get()).unwrap_unchecked()
+            },
+            frame_mode,
+            remote_transmit_request: e0.rtr().get(),
+            error_state_indicator: e0.esi().get(),
+            timestamp: e1.txts().get(),
+            message_marker: e1.mm().get(),
+            event_type,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    #[test]
+    fn test_tx_event_decoding() {
+        // Standard id 0x123, FD frame with bit rate switch, 12 bytes, marker 5
+        let mut element = [
+            0x123u32 << 18,
+            (5 << 24) | (0b01 << 22) | (0b11 << 20) | (9 << 16) | 0xBEEF,
+        ];
+        let event = TxEventElement::new(element.as_mut_ptr().cast()).read();
+
+        assert_eq!(
+            event.id,
+            MessageId {
+                data: 0x123,
+                length: MessageIdLength::Standard
+            }
+        );
+        assert_eq!(event.data_length_code, DataLenghtCode::_12);
+        assert_eq!(event.frame_mode, FrameMode::FdLongAndFast);
+        assert!(!event.remote_transmit_request);
+        assert_eq!(event.timestamp, 0xBEEF);
+        assert_eq!(event.message_marker, 5);
+        assert_eq!(event.event_type, TxEventType::Transmitted);
+
+        // Extended remote frame, transmitted in spite of cancellation
+        let mut element = [(0b011 << 29) | 0x1234_5678, (0b10 << 22) | (8 << 16)];
+        let event = TxEventElement::new(element.as_mut_ptr().cast()).read();
+
+        assert_eq!(
+            event.id,
+            MessageId {
+                data: 0x1234_5678,
+                length: MessageIdLength::Extended
+            }
+        );
+        assert_eq!(event.frame_mode, FrameMode::Standard);
+        assert!(event.remote_transmit_request);
+        assert!(!event.error_state_indicator);
+        assert_eq!(
+            event.event_type,
+            TxEventType::TransmittedInSpiteOfCancellation
+        );
+    }
+}
@@ -9,11 +9,12 @@ mod can_node;
 mod frame;
 mod internals;
 pub mod msg;
-pub mod pin_map;
+mod ram_layout;
 mod reg;
 
 pub use baud_rate::*;
 pub use can_module::*;
 pub use can_node::*;
-pub use frame::Frame;
-pub use msg::{FrameMode, MessageId};
+pub use frame::{Frame, OwnedFrame};
+pub use msg::{Filter, FilterElementConfiguration, FilterType, FrameMode, MessageId};
+pub use ram_layout::*;
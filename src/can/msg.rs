@@ -40,6 +40,25 @@ impl From<embedded_can::ExtendedId> for MessageId {
     }
 }
 
+impl TryFrom<MessageId> for embedded_can::Id {
+    type Error = ();
+
+    fn try_from(id: MessageId) -> Result<Self, Self::Error> {
+        match id.length {
+            MessageIdLength::Standard => {
+                let id = u16::try_from(id.data).map_err(|_| ())?;
+                embedded_can::StandardId::new(id)
+                    .map(Self::Standard)
+                    .ok_or(())
+            }
+            MessageIdLength::Extended => embedded_can::ExtendedId::new(id.data)
+                .map(Self::Extended)
+                .ok_or(()),
+            MessageIdLength::Both => Err(()),
+        }
+    }
+}
+
 #[derive(PartialEq, Debug, Default, Copy, Clone)]
 pub enum FrameMode {
     #[default]
@@ -53,6 +72,14 @@ pub struct RxMessage {
     pub buffer_id: RxBufferId,
     pub id: MessageId,
     pub data_length_code: DataLenghtCode,
+    /// The frame is a remote frame (RTR): it requests `data_length_code` data bytes and does not
+    /// carry any data
+    pub remote_transmit_request: bool,
+    /// Number of data bytes received (zero for remote frames). Smaller than the data length code
+    /// if the frame did not fit into the data field of the Rx element.
+    pub len: usize,
+    /// Rx timestamp, captured at the start of frame
+    pub timestamp: u16,
     pub from: ReadFrom,
     pub frame_mode: FrameMode,
 }
@@ -87,6 +114,32 @@ impl Default for TxMessage {
     }
 }
 
+/// Event type of a Tx event FIFO element (ET)
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TxEventType {
+    /// The frame has been transmitted
+    Transmitted,
+    /// The frame has been transmitted even if its cancellation had been requested
+    TransmittedInSpiteOfCancellation,
+    /// Reserved value, never written by the hardware
+    Reserved,
+}
+
+/// Element of the Tx event FIFO, stored after the transmission of a frame
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TxEvent {
+    pub id: MessageId,
+    pub data_length_code: DataLenghtCode,
+    pub frame_mode: FrameMode,
+    pub remote_transmit_request: bool,
+    pub error_state_indicator: bool,
+    /// Tx timestamp, captured at the start of frame
+    pub timestamp: u16,
+    /// Copy of the message marker of the Tx buffer, which is the Tx buffer id
+    pub message_marker: u8,
+    pub event_type: TxEventType,
+}
+
 #[derive(Clone, Copy)]
 pub enum FrameType {
     Receive,
@@ -142,6 +195,14 @@ impl RxBufferId {
     }
 }
 
+impl TryFrom<u8> for RxBufferId {
+    type Error = ();
+
+    fn try_from(value: u8) -> Result<Self, Self::Error> {
+        Self::new(value).ok_or(())
+    }
+}
+
 impl From<RxBufferId> for u8 {
     fn from(value: RxBufferId) -> Self {
         value.0
@@ -160,6 +221,66 @@ impl From<RxBufferId> for u32 {
     }
 }
 
+/// New data flags of the dedicated Rx buffers (NDAT1, NDAT2): bit n is set when buffer n holds a
+/// received message which has not been read yet
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct RxBuffersNewData(u64);
+
+impl RxBuffersNewData {
+    pub(crate) fn new(bits: u64) -> Self {
+        Self(bits)
+    }
+
+    #[must_use]
+    pub fn contains(self, buffer: RxBufferId) -> bool {
+        self.0 & (1 << buffer.0) != 0
+    }
+
+    #[must_use]
+    pub fn is_empty(self) -> bool {
+        self.0 == 0
+    }
+
+    #[must_use]
+    pub fn bits(self) -> u64 {
+        self.0
+    }
+
+    /// Buffers with new data, in increasing order
+    #[must_use]
+    pub fn iter(self) -> RxBuffersNewDataIter {
+        RxBuffersNewDataIter(self.0)
+    }
+}
+
+impl IntoIterator for RxBuffersNewData {
+    type Item = RxBufferId;
+    type IntoIter = RxBuffersNewDataIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+/// Iterator over the dedicated Rx buffers with new data
+#[derive(Debug, Clone)]
+pub struct RxBuffersNewDataIter(u64);
+
+impl Iterator for RxBuffersNewDataIter {
+    type Item = RxBufferId;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.0 == 0 {
+            return None;
+        }
+
+        let index = self.0.trailing_zeros();
+        self.0 &= self.0 - 1;
+
+        u8::try_from(index).ok().and_then(RxBufferId::new)
+    }
+}
+
 #[derive(Clone, Copy, Debug)]
 pub enum ReadFrom {
     RxFifo0,
@@ -181,10 +302,15 @@ pub enum FilterElementConfiguration {
 
 #[derive(Clone, Copy)]
 pub enum FilterType {
+    /// Accept IDs from `id1` to `id2`. Extended IDs are masked with XIDAM first.
     Range,
     Dualid,
     Classic,
+    /// Disable the filter element
     None,
+    /// Accept IDs from `id1` to `id2`, without masking extended IDs with XIDAM (EFT = 3).
+    /// Same as [`FilterType::Range`] for standard IDs.
+    RangeWithoutMask,
 }
 
 #[derive(Clone, Copy)]
@@ -196,3 +322,40 @@ pub struct Filter {
     pub id2: u32,
     pub rx_buffer_offset: RxBufferId,
 }
+
+impl Filter {
+    /// Filter element storing the frames with ID `id` in the dedicated Rx buffer `buffer`
+    #[must_use]
+    pub fn store_in_rx_buffer(number: u8, id: u32, buffer: RxBufferId) -> Self {
+        Self {
+            number,
+            element_configuration: FilterElementConfiguration::StoreInRxBuffer,
+            // The filter type is ignored when storing into a dedicated Rx buffer
+            typ: FilterType::Classic,
+            id1: id,
+            id2: 0,
+            rx_buffer_offset: buffer,
+        }
+    }
+}
+
+#[cfg(test)]
+mod test {
+    use super::{RxBufferId, RxBuffersNewData};
+
+    #[test]
+    fn test_rx_buffers_new_data() {
+        let new_data = RxBuffersNewData::new((1 << 63) | (1 << 32) | (1 << 5) | 1);
+        let id = |n: u8| RxBufferId::try_from(n).unwrap();
+
+        assert!(new_data.contains(id(5)));
+        assert!(!new_data.contains(id(6)));
+
+        let ids: Vec<u8> = new_data.into_iter().map(u8::from).collect();
+        assert_eq!(ids, [0, 5, 32, 63]);
+
+        assert!(RxBuffersNewData::default().is_empty());
+        assert_eq!(RxBuffersNewData::default().iter().next(), None);
+        assert!(RxBufferId::try_from(64).is_err());
+    }
+}
@@ -0,0 +1,367 @@
+//! Message RAM layout allocator.
+//!
+//! Each CAN module shares a single message RAM between its four nodes. Every node needs
+//! space for its filter lists, Rx FIFOs, Rx buffers, Tx event FIFO and Tx buffers.
+//! [`MessageRamLayout::new`] places all of them one after the other, without overlaps,
+//! and checks that they fit in the message RAM. It is a `const fn`, so a layout can be
+//! computed (and rejected) at compile time:
+//!
+//! ```ignore
+//! const LAYOUT: MessageRamLayout = match MessageRamLayout::new([NODE0, NODE1, NODE2, NODE3]) {
+//!     Ok(layout) => layout,
+//!     Err(_) => panic!("message RAM layout does not fit"),
+//! };
+//! ```
+
+use crate::can::DataFieldSize;
+
+/// Size in bytes of the message RAM of a module. The message RAM is located right before the
+/// module registers, which start 32 KB after the RAM base address.
+pub const MESSAGE_RAM_SIZE: u32 = 0x8000;
+
+pub(crate) const STANDARD_FILTER_ELEMENT_SIZE: u32 = 4;
+pub(crate) const EXTENDED_FILTER_ELEMENT_SIZE: u32 = 8;
+pub(crate) const TX_EVENT_ELEMENT_SIZE: u32 = 8;
+const ELEMENT_HEADER_SIZE: u32 = 8;
+
+pub(crate) const MAX_STANDARD_FILTERS: u8 = 128;
+pub(crate) const MAX_EXTENDED_FILTERS: u8 = 64;
+const MAX_RX_FIFO_SIZE: u8 = 64;
+const MAX_RX_BUFFERS: u8 = 64;
+const MAX_TX_EVENT_FIFO_SIZE: u8 = 32;
+const MAX_TX_BUFFERS: u8 = 32;
+
+/// Number of elements (and data field sizes) a node needs in the message RAM
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeRamRequirements {
+    pub standard_filters: u8,
+    pub extended_filters: u8,
+    pub rx_fifo0_size: u8,
+    pub rx_fifo0_data_field_size: DataFieldSize,
+    pub rx_fifo1_size: u8,
+    pub rx_fifo1_data_field_size: DataFieldSize,
+    pub rx_buffers: u8,
+    pub rx_buffer_data_field_size: DataFieldSize,
+    pub tx_event_fifo_size: u8,
+    /// Dedicated Tx buffers plus Tx FIFO/queue elements
+    pub tx_buffers: u8,
+    pub tx_buffer_data_field_size: DataFieldSize,
+}
+
+impl NodeRamRequirements {
+    /// A node which does not use the message RAM
+    pub const UNUSED: Self = Self {
+        standard_filters: 0,
+        extended_filters: 0,
+        rx_fifo0_size: 0,
+        rx_fifo0_data_field_size: DataFieldSize::_8,
+        rx_fifo1_size: 0,
+        rx_fifo1_data_field_size: DataFieldSize::_8,
+        rx_buffers: 0,
+        rx_buffer_data_field_size: DataFieldSize::_8,
+        tx_event_fifo_size: 0,
+        tx_buffers: 0,
+        tx_buffer_data_field_size: DataFieldSize::_8,
+    };
+
+    const fn is_valid(&self) -> bool {
+        self.standard_filters <= MAX_STANDARD_FILTERS
+            && self.extended_filters <= MAX_EXTENDED_FILTERS
+            && self.rx_fifo0_size <= MAX_RX_FIFO_SIZE
+            && self.rx_fifo1_size <= MAX_RX_FIFO_SIZE
+            && self.rx_buffers <= MAX_RX_BUFFERS
+            && self.tx_event_fifo_size <= MAX_TX_EVENT_FIFO_SIZE
+            && self.tx_buffers <= MAX_TX_BUFFERS
+    }
+}
+
+impl Default for NodeRamRequirements {
+    fn default() -> Self {
+        Self::UNUSED
+    }
+}
+
+/// Start addresses of the sections of a node, relative to the message RAM base address.
+/// They can be used in `TxConfig`, `RxConfig` and in the filter list setup.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct NodeRamLayout {
+    pub standard_filter_list_start_address: u16,
+    pub extended_filter_list_start_address: u16,
+    pub rx_fifo0_start_address: u16,
+    pub rx_fifo1_start_address: u16,
+    pub rx_buffers_start_address: u16,
+    pub tx_event_fifo_start_address: u16,
+    pub tx_buffers_start_address: u16,
+    /// First address after the node sections
+    pub end_address: u16,
+    /// Element counts and data field sizes the sections are reserved for
+    pub requirements: NodeRamRequirements,
+}
+
+impl NodeRamLayout {
+    /// Check that the sections are word aligned, in allocation order, inside the message RAM and
+    /// large enough for [`Self::requirements`]
+    pub(crate) fn is_valid(&self) -> bool {
+        let requirements = &self.requirements;
+        if !requirements.is_valid() {
+            return false;
+        }
+
+        let addresses = [
+            self.standard_filter_list_start_address,
+            self.extended_filter_list_start_address,
+            self.rx_fifo0_start_address,
+            self.rx_fifo1_start_address,
+            self.rx_buffers_start_address,
+            self.tx_event_fifo_start_address,
+            self.tx_buffers_start_address,
+            self.end_address,
+        ];
+
+        // Size in bytes of each section, in the order of the addresses above
+        let sizes = [
+            u32::from(requirements.standard_filters) * STANDARD_FILTER_ELEMENT_SIZE,
+            u32::from(requirements.extended_filters) * EXTENDED_FILTER_ELEMENT_SIZE,
+            u32::from(requirements.rx_fifo0_size)
+                * element_size(requirements.rx_fifo0_data_field_size),
+            u32::from(requirements.rx_fifo1_size)
+                * element_size(requirements.rx_fifo1_data_field_size),
+            u32::from(requirements.rx_buffers)
+                * element_size(requirements.rx_buffer_data_field_size),
+            u32::from(requirements.tx_event_fifo_size) * TX_EVENT_ELEMENT_SIZE,
+            u32::from(requirements.tx_buffers)
+                * element_size(requirements.tx_buffer_data_field_size),
+        ];
+
+        addresses.iter().all(|address| address % 4 == 0)
+            && addresses
+                .windows(2)
+                .zip(sizes)
+                .all(|(pair, size)| match pair {
+                    [start, next] => u32::from(*start) + size <= u32::from(*next),
+                    _ => false,
+                })
+            && u32::from(self.end_address) <= MESSAGE_RAM_SIZE
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RamLayoutError {
+    /// An element count of the node exceeds the hardware limits
+    InvalidElementCount { node: u8 },
+    /// The layout needs more than [`MESSAGE_RAM_SIZE`] bytes
+    OutOfMemory { required: u32 },
+}
+
+/// Non overlapping layout of the message RAM of a module
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MessageRamLayout {
+    pub nodes: [NodeRamLayout; 4],
+}
+
+impl MessageRamLayout {
+    pub const fn new(requirements: [NodeRamRequirements; 4]) -> Result<Self, RamLayoutError> {
+        let [node0, node1, node2, node3] = requirements;
+
+        let node0 = match allocate_node(&node0, 0, 0) {
+            Ok(layout) => layout,
+            Err(e) => return Err(e),
+        };
+        let node1 = match allocate_node(&node1, 1, node0.end_address) {
+            Ok(layout) => layout,
+            Err(e) => return Err(e),
+        };
+        let node2 = match allocate_node(&node2, 2, node1.end_address) {
+            Ok(layout) => layout,
+            Err(e) => return Err(e),
+        };
+        let node3 = match allocate_node(&node3, 3, node2.end_address) {
+            Ok(layout) => layout,
+            Err(e) => return Err(e),
+        };
+
+        Ok(Self {
+            nodes: [node0, node1, node2, node3],
+        })
+    }
+
+    /// Number of bytes used by the layout
+    #[must_use]
+    pub const fn size(&self) -> u16 {
+        let [_, _, _, node3] = self.nodes;
+        node3.end_address
+    }
+}
+
+/// Place the sections of a node one after the other, starting from `start_address`
+const fn allocate_node(
+    node: &NodeRamRequirements,
+    node_index: u8,
+    start_address: u16,
+) -> Result<NodeRamLayout, RamLayoutError> {
+    if !node.is_valid() {
+        return Err(RamLayoutError::InvalidElementCount { node: node_index });
+    }
+
+    let mut address = start_address as u32;
+
+    let standard_filter_list_start_address = address;
+    address += node.standard_filters as u32 * STANDARD_FILTER_ELEMENT_SIZE;
+
+    let extended_filter_list_start_address = address;
+    address += node.extended_filters as u32 * EXTENDED_FILTER_ELEMENT_SIZE;
+
+    let rx_fifo0_start_address = address;
+    address += node.rx_fifo0_size as u32 * element_size(node.rx_fifo0_data_field_size);
+
+    let rx_fifo1_start_address = address;
+    address += node.rx_fifo1_size as u32 * element_size(node.rx_fifo1_data_field_size);
+
+    let rx_buffers_start_address = address;
+    address += node.rx_buffers as u32 * element_size(node.rx_buffer_data_field_size);
+
+    let tx_event_fifo_start_address = address;
+    address += node.tx_event_fifo_size as u32 * TX_EVENT_ELEMENT_SIZE;
+
+    let tx_buffers_start_address = address;
+    address += node.tx_buffers as u32 * element_size(node.tx_buffer_data_field_size);
+
+    if address > MESSAGE_RAM_SIZE {
+        return Err(RamLayoutError::OutOfMemory { required: address });
+    }
+
+    // All addresses fit in 16 bits, because they are not greater than MESSAGE_RAM_SIZE
+    #[allow(clippy::cast_possible_truncation)]
+    Ok(NodeRamLayout {
+        standard_filter_list_start_address: standard_filter_list_start_address as u16,
+        extended_filter_list_start_address: extended_filter_list_start_address as u16,
+        rx_fifo0_start_address: rx_fifo0_start_address as u16,
+        rx_fifo1_start_address: rx_fifo1_start_address as u16,
+        rx_buffers_start_address: rx_buffers_start_address as u16,
+        tx_event_fifo_start_address: tx_event_fifo_start_address as u16,
+        tx_buffers_start_address: tx_buffers_start_address as u16,
+        end_address: address as u16,
+        requirements: *node,
+    })
+}
+
+/// Size in bytes of an Rx or Tx buffer element
+const fn element_size(data_field_size: DataFieldSize) -> u32 {
+    ELEMENT_HEADER_SIZE + data_field_size.to_bytes() as u32
+}
+
+#[cfg(test)]
+mod test {
+    use super::*;
+
+    const NODE: NodeRamRequirements = NodeRamRequirements {
+        standard_filters: 2,
+        extended_filters: 1,
+        rx_fifo0_size: 4,
+        rx_fifo0_data_field_size: DataFieldSize::_8,
+        rx_fifo1_size: 0,
+        rx_fifo1_data_field_size: DataFieldSize::_8,
+        rx_buffers: 2,
+        rx_buffer_data_field_size: DataFieldSize::_64,
+        tx_event_fifo_size: 1,
+        tx_buffers: 2,
+        tx_buffer_data_field_size: DataFieldSize::_12,
+    };
+
+    // Layouts can be computed at compile time
+    const LAYOUT: MessageRamLayout = match MessageRamLayout::new([
+        NODE,
+        NodeRamRequirements::UNUSED,
+        NODE,
+        NodeRamRequirements::UNUSED,
+    ]) {
+        Ok(layout) => layout,
+        Err(_) => panic!("invalid layout"),
+    };
+
+    #[test]
+    fn test_node_layout() {
+        let node0 = LAYOUT.nodes[0];
+        assert_eq!(node0.standard_filter_list_start_address, 0);
+        assert_eq!(node0.extended_filter_list_start_address, 8);
+        assert_eq!(node0.rx_fifo0_start_address, 16);
+        assert_eq!(node0.rx_fifo1_start_address, 16 + 4 * 16);
+        assert_eq!(node0.rx_buffers_start_address, 80);
+        assert_eq!(node0.tx_event_fifo_start_address, 80 + 2 * 72);
+        assert_eq!(node0.tx_buffers_start_address, 224 + 8);
+        assert_eq!(node0.end_address, 232 + 2 * 20);
+
+        // Unused nodes take no space
+        let node1 = LAYOUT.nodes[1];
+        assert_eq!(node1.standard_filter_list_start_address, node0.end_address);
+        assert_eq!(node1.end_address, node0.end_address);
+
+        let node2 = LAYOUT.nodes[2];
+        assert_eq!(node2.standard_filter_list_start_address, node0.end_address);
+        assert_eq!(node2.end_address, 2 * node0.end_address);
+        assert_eq!(LAYOUT.size(), 2 * node0.end_address);
+    }
+
+    #[test]
+    fn test_node_layout_validation() {
+        let node0 = LAYOUT.nodes[0];
+        assert!(node0.is_valid());
+        assert_eq!(node0.requirements, NODE);
+
+        let overlapping = NodeRamLayout {
+            rx_fifo1_start_address: node0.rx_fifo0_start_address - 4,
+            ..node0
+        };
+        assert!(!overlapping.is_valid());
+
+        let unaligned = NodeRamLayout {
+            tx_buffers_start_address: node0.tx_buffers_start_address + 2,
+            ..node0
+        };
+        assert!(!unaligned.is_valid());
+
+        // Rx FIFO 0 is reserved for 4 elements, it cannot hold a fifth one
+        let too_small = NodeRamLayout {
+            requirements: NodeRamRequirements {
+                rx_fifo0_size: 5,
+                ..NODE
+            },
+            ..node0
+        };
+        assert!(!too_small.is_valid());
+
+        let too_many_tx_buffers = NodeRamLayout {
+            requirements: NodeRamRequirements {
+                tx_buffers: 33,
+                ..NODE
+            },
+            end_address: 0x7000,
+            ..node0
+        };
+        assert!(!too_many_tx_buffers.is_valid());
+    }
+
+    #[test]
+    fn test_invalid_layout() {
+        let too_many_filters = NodeRamRequirements {
+            standard_filters: 129,
+            ..NODE
+        };
+        assert_eq!(
+            MessageRamLayout::new([NODE, NODE, too_many_filters, NODE]),
+            Err(RamLayoutError::InvalidElementCount { node: 2 })
+        );
+
+        let big = NodeRamRequirements {
+            rx_fifo0_size: 64,
+            rx_fifo0_data_field_size: DataFieldSize::_64,
+            rx_fifo1_size: 64,
+            rx_fifo1_data_field_size: DataFieldSize::_64,
+            ..NODE
+        };
+        assert!(matches!(
+            MessageRamLayout::new([big, big, big, big]),
+            Err(RamLayoutError::OutOfMemory { .. })
+        ));
+    }
+}
@@ -2,8 +2,10 @@
 #![allow(clippy::undocumented_unsafe_blocks)]
 #![allow(clippy::cast_possible_truncation)]
 
+pub(crate) mod msg_filter;
 pub(crate) mod msg_rx;
 pub(crate) mod msg_tx;
+pub(crate) mod msg_tx_event;
 
 use core::convert::From;
 use core::marker::PhantomData;
@@ -36,6 +38,36 @@ pub(crate) trait Write: Access {}
 impl Write for RW {}
 impl Write for W {}
 
+/// Copy `length` bytes of the message RAM at `src` to `dst`.
+/// With the tracing feature, the message RAM is read byte per byte through the tracing
+/// functions, like the peripheral registers. The unit tests of the elements use local memory.
+#[inline(always)]
+pub(crate) unsafe fn read_message_ram(src: *const u8, dst: *mut u8, length: usize) {
+    #[cfg(all(feature = "tracing", not(test)))]
+    for i in 0..length {
+        let byte = crate::tracing::read_volatile(src as usize + i, 1) as u8;
+        unsafe { dst.wrapping_add(i).write(byte) };
+    }
+    #[cfg(not(all(feature = "tracing", not(test))))]
+    unsafe {
+        core::ptr::copy_nonoverlapping(src, dst, length);
+    }
+}
+
+/// Copy `length` bytes from `src` to the message RAM at `dst`, see [`read_message_ram`]
+#[inline(always)]
+pub(crate) unsafe fn write_message_ram(src: *const u8, dst: *mut u8, length: usize) {
+    #[cfg(all(feature = "tracing", not(test)))]
+    for i in 0..length {
+        let byte = unsafe { src.wrapping_add(i).read() };
+        crate::tracing::write_volatile(dst as usize + i, 1, byte.into());
+    }
+    #[cfg(not(all(feature = "tracing", not(test))))]
+    unsafe {
+        core::ptr::copy_nonoverlapping(src, dst, length);
+    }
+}
+
 #[derive(Copy, Clone, PartialEq, Eq)]
 pub(crate) struct Reg<T, A: Access> {
     ptr: *mut u8,
@@ -152,6 +184,12 @@ impl<T: RegValue, A: Read> Reg<T, A> {
     #[inline(always)]
     #[must_use]
     pub(crate) unsafe fn read(&self) -> T {
+        #[cfg(all(feature = "tracing", not(test)))]
+        let v = T::DataType::cast_from(crate::tracing::read_volatile(
+            self.ptr as usize,
+            core::mem::size_of::<T::DataType>(),
+        ));
+        #[cfg(not(all(feature = "tracing", not(test))))]
         let v = unsafe { (self.ptr as *mut T::DataType).read_volatile() };
         T::new(v, 0.into())
     }
@@ -160,6 +198,13 @@ impl<T: RegValue, A: Read> Reg<T, A> {
 impl<T: RegValue, A: Write> Reg<T, A> {
     #[inline(always)]
     pub(crate) unsafe fn write(&self, reg_value: T) {
+        #[cfg(all(feature = "tracing", not(test)))]
+        crate::tracing::write_volatile(
+            self.ptr as usize,
+            core::mem::size_of::<T::DataType>(),
+            reg_value.data().into(),
+        );
+        #[cfg(not(all(feature = "tracing", not(test))))]
         unsafe {
             (self.ptr as *mut T::DataType).write_volatile(reg_value.data());
         }
@@ -291,8 +291,110 @@ macro_rules! pin {
 }
 use pin;
 
+/// CAN receive pins are inputs, the node selects one of them with its RXSEL multiplexer
+macro_rules! can_rx_pin {
+    ( $(#[$docs:meta])* <$name:ident> [$(
+        $(#[$attr:meta])* $PX:ident<$Sel:ident>,
+    )*], ) => {
+        #[derive(Debug)]
+        $(#[$docs])*
+        pub enum $name {
+            $(
+                $(#[$attr])*
+                $PX(gpio::$PX<$crate::gpio::Input>),
+            )*
+        }
+
+        impl crate::Sealed for $name { }
+
+        #[allow(unreachable_patterns)]
+        impl $crate::gpio::ReadPin for $name {
+            fn is_low(&self) -> bool {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        Self::$PX(p) => p.is_low(),
+                    )*
+                    _ => false,
+                }
+            }
+        }
+
+        #[allow(unreachable_patterns)]
+        impl $crate::gpio::PinPull for $name {
+            fn set_internal_resistor(&mut self, _pull: $crate::gpio::Pull) {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        Self::$PX(p) => p.set_internal_resistor(_pull),
+                    )*
+                    _ => {}
+                }
+            }
+        }
+
+        #[allow(unreachable_patterns)]
+        impl $crate::gpio::PinSpeed for $name {
+            fn set_speed(&mut self, _speed: $crate::gpio::Speed) {
+                match self {
+                    $(
+                        $(#[$attr])*
+                        Self::$PX(p) => p.set_speed(_speed),
+                    )*
+                    _ => {}
+                }
+            }
+        }
+
+        impl $crate::gpio::alt::CanRxPin for $name {
+            fn rx_select(&self) -> $crate::can::RxSel {
+                match *self {
+                    $(
+                        $(#[$attr])*
+                        Self::$PX(_) => $crate::can::RxSel::$Sel,
+                    )*
+                }
+            }
+        }
+
+        $(
+            $(#[$attr])*
+            impl From<gpio::$PX<$crate::gpio::Input>> for $name {
+                fn from(p: gpio::$PX<$crate::gpio::Input>) -> Self {
+                    Self::$PX(p)
+                }
+            }
+
+            $(#[$attr])*
+            #[allow(irrefutable_let_patterns)]
+            impl<MODE> TryFrom<$name> for gpio::$PX<MODE>
+            where
+                MODE: $crate::gpio::PinMode,
+            {
+                type Error = ();
+
+                fn try_from(a: $name) -> Result<Self, Self::Error> {
+                    if let $name::$PX(p) = a {
+                        Ok(p.into_mode())
+                    } else {
+                        Err(())
+                    }
+                }
+            }
+        )*
+    };
+}
+use can_rx_pin;
+
 // CAN pins
-pub trait CanCommon {
-    type Rx;
-    type Tx;
+/// Receive pin of a CAN node
+pub trait CanRxPin: crate::gpio::PinPull + crate::gpio::PinSpeed {
+    /// Input of the node receive multiplexer (NPCR.RXSEL) the pin is connected to
+    fn rx_select(&self) -> crate::can::RxSel;
+}
+
+/// Pins which can be connected to node `N` of a CAN module
+pub trait CanPins<N> {
+    type Rx: CanRxPin;
+    type Tx: crate::gpio::PinSpeed;
 }
@@ -4,19 +4,233 @@ use crate::gpio::{self, NoPin, OpenDrain, PushPull};
 pub mod can0 {
     use super::*;
 
-    pin! {
-        <Rx, PushPull> for no:NoPin, [
-            // FIXME Alternate function here?
-            P20_7<1>, // CAN00:RXDB = P20.7:IN
-        ],
+    pub mod node0 {
+        use super::*;
 
-        <Tx, PushPull> for no:NoPin, [
-            P20_8<5>, // CAN00:TXD = P20.8:ALT(5)
-        ],
+        can_rx_pin! {
+            <Rx> [
+                P02_1<_A>, // CAN00:RXDA = P02.1:IN
+                P20_7<_B>, // CAN00:RXDB = P20.7:IN
+                P12_0<_C>, // CAN00:RXDC = P12.0:IN
+                P33_12<_D>, // CAN00:RXDD = P33.12:IN
+                P33_7<_E>, // CAN00:RXDE = P33.7:IN
+                P34_2<_G>, // CAN00:RXDG = P34.2:IN
+            ],
+        }
+
+        pin! {
+            <Tx, PushPull> for [
+                P02_0<5>, // CAN00:TXD = P02.0:ALT(5)
+                P12_1<5>, // CAN00:TXD = P12.1:ALT(5)
+                P20_8<5>, // CAN00:TXD = P20.8:ALT(5)
+                P33_8<5>, // CAN00:TXD = P33.8:ALT(5)
+                P33_13<5>, // CAN00:TXD = P33.13:ALT(5)
+                P34_1<4>, // CAN00:TXD = P34.1:ALT(4)
+            ],
+        }
+
+        impl CanPins<crate::can::Node0> for crate::can::Module0 {
+            type Rx = Rx;
+            type Tx = Tx;
+        }
+    }
+
+    pub mod node1 {
+        use super::*;
+
+        can_rx_pin! {
+            <Rx> [
+                P15_3<_A>, // CAN01:RXDA = P15.3:IN
+                P14_1<_B>, // CAN01:RXDB = P14.1:IN
+                P01_4<_C>, // CAN01:RXDC = P01.4:IN
+                P33_10<_D>, // CAN01:RXDD = P33.10:IN
+                P02_10<_E>, // CAN01:RXDE = P02.10:IN
+            ],
+        }
+
+        pin! {
+            <Tx, PushPull> for [
+                P01_3<5>, // CAN01:TXD = P01.3:ALT(5)
+                P02_9<5>, // CAN01:TXD = P02.9:ALT(5)
+                P14_0<5>, // CAN01:TXD = P14.0:ALT(5)
+                P15_2<5>, // CAN01:TXD = P15.2:ALT(5)
+                P33_9<5>, // CAN01:TXD = P33.9:ALT(5)
+            ],
+        }
+
+        impl CanPins<crate::can::Node1> for crate::can::Module0 {
+            type Rx = Rx;
+            type Tx = Tx;
+        }
+    }
+
+    pub mod node2 {
+        use super::*;
+
+        can_rx_pin! {
+            <Rx> [
+                P15_1<_A>, // CAN02:RXDA = P15.1:IN
+                P02_3<_B>, // CAN02:RXDB = P02.3:IN
+                P32_6<_C>, // CAN02:RXDC = P32.6:IN
+                P14_8<_D>, // CAN02:RXDD = P14.8:IN
+                P10_2<_E>, // CAN02:RXDE = P10.2:IN
+            ],
+        }
+
+        pin! {
+            <Tx, PushPull> for [
+                P02_2<5>, // CAN02:TXD = P02.2:ALT(5)
+                P10_3<6>, // CAN02:TXD = P10.3:ALT(6)
+                P14_10<5>, // CAN02:TXD = P14.10:ALT(5)
+                P15_0<5>, // CAN02:TXD = P15.0:ALT(5)
+                P32_5<6>, // CAN02:TXD = P32.5:ALT(6)
+            ],
+        }
+
+        impl CanPins<crate::can::Node2> for crate::can::Module0 {
+            type Rx = Rx;
+            type Tx = Tx;
+        }
+    }
+
+    pub mod node3 {
+        use super::*;
+
+        can_rx_pin! {
+            <Rx> [
+                P00_3<_A>, // CAN03:RXDA = P00.3:IN
+                P32_2<_B>, // CAN03:RXDB = P32.2:IN
+                P20_0<_C>, // CAN03:RXDC = P20.0:IN
+                P11_10<_D>, // CAN03:RXDD = P11.10:IN
+                P20_9<_E>, // CAN03:RXDE = P20.9:IN
+            ],
+        }
+
+        pin! {
+            <Tx, PushPull> for [
+                P00_2<5>, // CAN03:TXD = P00.2:ALT(5)
+                P11_12<5>, // CAN03:TXD = P11.12:ALT(5)
+                P20_3<5>, // CAN03:TXD = P20.3:ALT(5)
+                P20_10<5>, // CAN03:TXD = P20.10:ALT(5)
+                P32_3<5>, // CAN03:TXD = P32.3:ALT(5)
+            ],
+        }
+
+        impl CanPins<crate::can::Node3> for crate::can::Module0 {
+            type Rx = Rx;
+            type Tx = Tx;
+        }
+    }
+}
+
+pub mod can1 {
+    use super::*;
+
+    pub mod node0 {
+        use super::*;
+
+        can_rx_pin! {
+            <Rx> [
+                P00_1<_A>, // CAN10:RXDA = P00.1:IN
+                P14_7<_B>, // CAN10:RXDB = P14.7:IN
+                P23_0<_C>, // CAN10:RXDC = P23.0:IN
+                P13_1<_D>, // CAN10:RXDD = P13.1:IN
+            ],
+        }
+
+        pin! {
+            <Tx, PushPull> for [
+                P00_0<5>, // CAN10:TXD = P00.0:ALT(5)
+                P13_0<7>, // CAN10:TXD = P13.0:ALT(7)
+                P14_9<4>, // CAN10:TXD = P14.9:ALT(4)
+                P23_1<5>, // CAN10:TXD = P23.1:ALT(5)
+            ],
+        }
+
+        impl CanPins<crate::can::Node0> for crate::can::Module1 {
+            type Rx = Rx;
+            type Tx = Tx;
+        }
     }
 
-    impl CanCommon for crate::pac::can0::Can0 {
-        type Rx = Rx;
-        type Tx = Tx;
+    pub mod node1 {
+        use super::*;
+
+        can_rx_pin! {
+            <Rx> [
+                P02_4<_A>, // CAN11:RXDA = P02.4:IN
+                P00_5<_B>, // CAN11:RXDB = P00.5:IN
+                P23_7<_C>, // CAN11:RXDC = P23.7:IN
+                P11_7<_D>, // CAN11:RXDD = P11.7:IN
+            ],
+        }
+
+        pin! {
+            <Tx, PushPull> for [
+                P00_4<3>, // CAN11:TXD = P00.4:ALT(3)
+                P02_5<2>, // CAN11:TXD = P02.5:ALT(2)
+                P11_0<5>, // CAN11:TXD = P11.0:ALT(5)
+                P23_6<5>, // CAN11:TXD = P23.6:ALT(5)
+            ],
+        }
+
+        impl CanPins<crate::can::Node1> for crate::can::Module1 {
+            type Rx = Rx;
+            type Tx = Tx;
+        }
+    }
+
+    pub mod node2 {
+        use super::*;
+
+        can_rx_pin! {
+            <Rx> [
+                P20_6<_A>, // CAN12:RXDA = P20.6:IN
+                P10_8<_B>, // CAN12:RXDB = P10.8:IN
+                P23_3<_C>, // CAN12:RXDC = P23.3:IN
+                P11_8<_D>, // CAN12:RXDD = P11.8:IN
+            ],
+        }
+
+        pin! {
+            <Tx, PushPull> for [
+                P10_7<6>, // CAN12:TXD = P10.7:ALT(6)
+                P11_1<5>, // CAN12:TXD = P11.1:ALT(5)
+                P20_7<5>, // CAN12:TXD = P20.7:ALT(5)
+                P23_2<5>, // CAN12:TXD = P23.2:ALT(5)
+            ],
+        }
+
+        impl CanPins<crate::can::Node2> for crate::can::Module1 {
+            type Rx = Rx;
+            type Tx = Tx;
+        }
+    }
+
+    pub mod node3 {
+        use super::*;
+
+        can_rx_pin! {
+            <Rx> [
+                P14_7<_A>, // CAN13:RXDA = P14.7:IN
+                P33_5<_B>, // CAN13:RXDB = P33.5:IN
+                P22_5<_C>, // CAN13:RXDC = P22.5:IN
+                P11_13<_D>, // CAN13:RXDD = P11.13:IN
+            ],
+        }
+
+        pin! {
+            <Tx, PushPull> for [
+                P11_4<5>, // CAN13:TXD = P11.4:ALT(5)
+                P14_6<4>, // CAN13:TXD = P14.6:ALT(4)
+                P22_4<6>, // CAN13:TXD = P22.4:ALT(6)
+                P33_4<7>, // CAN13:TXD = P33.4:ALT(7)
+            ],
+        }
+
+        impl CanPins<crate::can::Node3> for crate::can::Module1 {
+            type Rx = Rx;
+            type Tx = Tx;
+        }
     }
 }
@@ -202,6 +202,9 @@ impl<const A: u8, Otype> marker::Readable for Alternate<A, Otype> {}
 
 impl marker::Active for Input {}
 
+// The pad driver mode (PDR) also applies to inputs, it selects their input levels
+impl marker::OutputSpeed for Input {}
+
 impl<Otype> marker::OutputSpeed for Output<Otype> {}
 
 impl<const A: u8, Otype> marker::OutputSpeed for Alternate<A, Otype> {}
@@ -354,16 +357,26 @@ impl<const P: PortIndex, const N: PinIndex, MODE> Pin<P, N, MODE>
 where
     MODE: marker::OutputSpeed,
 {
-    /// Set pin speed
+    /// Set pin speed, [`Speed::Low`] to [`Speed::VeryHigh`] select the CMOS automotive pad
+    /// driver speed grades 1 to 4
     pub fn set_speed(&mut self, speed: Speed) {
-        let offset = 2 * { N };
-
-        // TODO Implement set speed
-        // unsafe {
-        //     (*Gpio::<P>::ptr())
-        //         .ospeedr
-        //         .modify(|r, w| w.bits((r.bits() & !(0b11 << offset)) | ((speed as u32) << offset)));
-        // }
+        // PDR0 holds the pad driver mode of pins 0 to 7, PDR1 of pins 8 to 15
+        let shift = 4 * (N % 8);
+        let value = (speed as u32) << shift;
+        let mask = 0xF << shift;
+
+        // SAFETY: Gpio::<P>::ptr() will panic if P is not a valid port index, all Port instances have the same layout as P00
+        let port = unsafe { (*Gpio::<P>::ptr()) };
+        let pdr = if N < 8 {
+            port.pdr0().ptr()
+        } else {
+            port.pdr1().ptr()
+        };
+
+        crate::scu::wdt_call::call_without_cpu_endinit(|| {
+            // SAFETY: PDx and PLx of pin N are RW, PDR is written with CPU ENDINIT cleared
+            unsafe { crate::intrinsics::load_modify_store(pdr, value, mask) };
+        });
     }
 
     /// Set pin speed
@@ -1,15 +1,20 @@
+use bw_r_drivers_tc37x::can::msg::{MessageIdLength, ReadFrom, RxBufferId, TxBufferId};
 use bw_r_drivers_tc37x::can::Tos;
 use bw_r_drivers_tc37x::can::{
-    config::NodeInterruptConfig,
-    pin_map::{PIN_RX_0_0_P20_7, PIN_TX_0_0_P20_8},
-    AutoBitTiming, BitTimingConfig, DataFieldSize, Interrupt, InterruptGroup, InterruptLine,
-    Module, Module0, Node0, NodeConfig, Pins, RxConfig, RxFifoMode, RxMode, TxConfig, TxMode,
+    config::NodeInterruptConfig, AutoBitTiming, BitTimingConfig, BusOffRecovery, ConfigError,
+    Configured, DataFieldSize, DrainReport, Enabled, ErrorStateChange, FastBitTimingConfig,
+    FilterError, FrameMode, Interrupt, InterruptGroup, InterruptLine, MessageId, Module, Module0,
+    Node, Node0, NodeConfig, NodeRamLayout, NodeRamRequirements, OperatingMode, OwnedFrame,
+    ReceiveError, RxConfig, RxFifoMode, RxMode, RxQueue, TakeNodeError,
+    TransceiverDelayCompensation, TransmitError, TxConfig, TxMode,
 };
 use bw_r_drivers_tc37x::cpu::Priority;
+use bw_r_drivers_tc37x::gpio::GpioExt;
 use bw_r_drivers_tc37x::pac;
 use bw_r_drivers_tc37x::tracing::log::Report;
+use embedded_can::{Frame, StandardId};
 
-use pac::{CAN0, SCU, SRC};
+use pac::{CAN0, P20, SCU, SRC};
 
 // TODO fix values of can_module.enable reads
 // TODO add report comments with actual registers' name
@@ -108,6 +113,9 @@ fn test_setup_can0() {
         0b110_0000_0000_0000_1010_0000_0011,
     );
 
+    // gfc0 for set_global_filter_configuration
+    report.expect_read(CAN0.n()[0].gfci().ptr(), 4, 0b0);
+
     let mut node = can_module
         .take_node(Node0, cfg)
         .expect("Cannot take can node");
@@ -141,7 +149,8 @@ fn test_setup_can0() {
         event_fifo_size: 1,
         tx_event_fifo_start_address: 0x400,
         tx_buffers_start_address: 0x440,
-    });
+    })
+    .expect("Cannot set up Tx");
 
     // rxesc0 for set_rx_buffer_data_field_size for setup_rx
     report.expect_read(CAN0.n()[0].rx().rxesci().ptr(), 4, 0b0);
@@ -189,12 +198,15 @@ fn test_setup_can0() {
         rx_fifo0_start_address: 0x100,
         rx_fifo1_start_address: 0x200,
         rx_buffers_start_address: 0x300,
-    });
+    })
+    .expect("Cannot set up Rx");
 
-    // clear_cpu_endinit
-    report.expect_read(SCU.wdtcpu()[0].wdtcpuycon0().ptr(), 4, 0b11);
+    let port20 = P20.split();
+    let tx = port20.p20_8.into_alternate::<5>();
+    let rx = port20.p20_7.into_input();
 
-    // set_cpu_endinit
+    // clear_cpu_endinit and set_cpu_endinit for the rx pad driver
+    report.expect_read(SCU.wdtcpu()[0].wdtcpuycon0().ptr(), 4, 0b11);
     report.expect_read(SCU.wdtcpu()[0].wdtcpuycon0().ptr(), 4, 0b10);
 
     report.expect_read(CAN0.n()[0].npcri().ptr(), 4, 0b0);
@@ -205,10 +217,7 @@ fn test_setup_can0() {
     // set_cpu_endinit
     report.expect_read(SCU.wdtcpu()[0].wdtcpuycon0().ptr(), 4, 0b10);
 
-    node.setup_pins(Some(&Pins {
-        tx: PIN_TX_0_0_P20_8,
-        rx: PIN_RX_0_0_P20_7,
-    }));
+    node.setup_pins(tx, rx);
 
     report.expect_read(CAN0.n()[0].grint2i().ptr(), 4, 0b0);
 
@@ -236,3 +245,896 @@ fn test_setup_can0() {
 
     insta::assert_snapshot!(report.take_log());
 }
+
+#[test]
+fn test_transmit_to_full_tx_fifo() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let mut node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+    report.take_log();
+
+    // txfqs0 with TFQF set, the put index must not be used
+    report.expect_read(CAN0.n()[0].tx().txfqsi().ptr(), 4, 1 << 21);
+
+    let frame = OwnedFrame::new(StandardId::new(0x123).unwrap(), &[1, 2, 3]).unwrap();
+    let result = embedded_can::nb::Can::transmit(&mut node, &frame);
+    assert!(matches!(result, Err(nb::Error::WouldBlock)));
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_transmit_fd_frame_larger_than_tx_buffers() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    expect_take_node0(&report);
+    let mut node = can_module
+        .take_node(Node0, node_config())
+        .expect("Cannot take can node");
+
+    // nbtp0 for set_nominal_bit_timing, dbtp0 for set_data_bit_timing, tdcr0 and dbtp0 for
+    // set_transceiver_delay_compensation, cccr0 for set_frame_mode
+    expect_get_mcan_frequency(&report);
+    report.expect_read(CAN0.n()[0].nbtpi().ptr(), 4, 0b0);
+    expect_get_mcan_frequency(&report);
+    report.expect_read(CAN0.n()[0].dbtpi().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tdcri().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].dbtpi().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b11);
+
+    node.set_bit_timing(
+        FrameMode::FdLong,
+        &node_config().baud_rate,
+        &FastBitTimingConfig::Auto(AutoBitTiming {
+            baud_rate: 2_000_000,
+            sample_point: 8_000,
+            sync_jump_width: 3,
+        }),
+        &TransceiverDelayCompensation::default(),
+    )
+    .expect("Cannot set bit timing");
+
+    // txesc0, txbc0, txbtie0, txefc0 and cccr0 for setup_tx
+    report.expect_read(CAN0.n()[0].tx().txesci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b100_0100_0000);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b100_0100_0000);
+    report.expect_read(CAN0.n()[0].tx().txbtiei().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbtiei().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].tx().txefci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txefci().ptr(), 4, 0b100_0000_0000);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b11_0000_0011);
+
+    node.setup_tx(&TxConfig {
+        mode: TxMode::Fifo,
+        dedicated_tx_buffers_number: 0,
+        fifo_queue_size: 2,
+        buffer_data_field_size: DataFieldSize::_8,
+        event_fifo_size: 1,
+        tx_event_fifo_start_address: 0x400,
+        tx_buffers_start_address: 0x440,
+    })
+    .expect("Cannot set up Tx");
+
+    // cccr0 for disable configuration change
+    for _ in 0..4 {
+        report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+    }
+
+    let node = node.lock_configuration();
+    report.take_log();
+
+    // txfqs0 with put index 1, the frame is rejected before anything is written to its element
+    report.expect_read(CAN0.n()[0].tx().txfqsi().ptr(), 4, 1 << 16);
+    report.expect_read(CAN0.n()[0].tx().txfqsi().ptr(), 4, 1 << 16);
+
+    let frame = OwnedFrame::new_fd(StandardId::new(0x123).unwrap(), &[0xAA; 64], false).unwrap();
+    assert_eq!(
+        node.transmit_frame(&frame),
+        Err(TransmitError::InvalidDataLength)
+    );
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_transmit_to_dedicated_tx_buffer_with_64_bytes_data_field() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+    let node = setup_dedicated_tx_buffers0(&report, node, DataFieldSize::_64);
+    report.take_log();
+
+    // txbrp0 without pending request, txesc0 with TBDS for 64 bytes
+    report.expect_read(CAN0.n()[0].tx().txbrpi().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txesci().ptr(), 4, 0b111);
+
+    // Buffer 1 starts 72 bytes (8 header and 64 data bytes) after buffer 0, at 0x440
+    let t0 = 0xF020_0488usize;
+    let t1 = 0xF020_048Cusize;
+    report.expect_read(t0, 4, 0x0);
+    report.expect_read(t1, 4, 0x0);
+    report.expect_read(t1, 4, 0x0080_0000);
+    report.expect_read(t0, 4, 0x048C_0000);
+    report.expect_read(t1, 4, 0x0180_0000);
+    report.expect_read(t1, 4, 0x0182_0000);
+
+    // txbar0 to request the transmission
+    report.expect_read(CAN0.n()[0].tx().txbari().ptr(), 4, 0b0);
+
+    let id = MessageId {
+        data: 0x123,
+        length: MessageIdLength::Standard,
+    };
+    let frame = bw_r_drivers_tc37x::can::Frame::new(id, &[0x11, 0x22]).unwrap();
+    let buffer = TxBufferId::try_from(1).unwrap();
+    assert_eq!(node.transmit_to(buffer, &frame), Ok(()));
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_cancel_tx_buffers() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+    report.take_log();
+
+    let buffer = |id: u8| TxBufferId::try_from(id).unwrap();
+
+    // txbcr0 is written with the bit of buffer 1 only
+    node.cancel_tx_buffer(buffer(1));
+
+    // txbrp0 with buffers 0 and 2 pending, both are cancelled
+    report.expect_read(CAN0.n()[0].tx().txbrpi().ptr(), 4, 0b101);
+    node.cancel_all_tx_buffers();
+
+    // txbrp0 with buffer 2 still pending
+    report.expect_read(CAN0.n()[0].tx().txbrpi().ptr(), 4, 0b100);
+    assert_eq!(node.tx_buffers_request_pending(), 0b100);
+    report.expect_read(CAN0.n()[0].tx().txbrpi().ptr(), 4, 0b100);
+    assert!(node.is_tx_buffer_request_pending(buffer(2)));
+
+    // txbcf0 with the cancellation of buffer 0 finished
+    report.expect_read(CAN0.n()[0].tx().txbcfi().ptr(), 4, 0b1);
+    assert!(node.is_tx_buffer_cancellation_finished(buffer(0)));
+
+    // txbto0 without any transmission
+    report.expect_read(CAN0.n()[0].tx().txbtoi().ptr(), 4, 0b0);
+    assert!(!node.is_tx_buffer_transmission_occured(buffer(2)));
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_bus_off_recovery_manual() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+    report.take_log();
+
+    // ir0 with BO set, psr0 with BO set
+    report.expect_read(CAN0.n()[0].iri().ptr(), 4, 1 << 25);
+    report.expect_read(CAN0.n()[0].psri().ptr(), 4, 1 << 7);
+
+    let mut changes = Vec::new();
+    node.handle_error_state_changes(|change| changes.push(change));
+    assert_eq!(changes, [ErrorStateChange::BusOff(true)]);
+
+    // Only the BO flag is cleared, INIT is left set
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_bus_off_recovery_on_error_handler() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let config = NodeConfig {
+        bus_off_recovery: BusOffRecovery::OnErrorHandler,
+        ..node_config()
+    };
+    let node = take_configured_node0(&report, &mut can_module, config, RxFifoMode::Blocking);
+    report.take_log();
+
+    // ir0 with BO set, psr0 with BO set
+    report.expect_read(CAN0.n()[0].iri().ptr(), 4, 1 << 25);
+    report.expect_read(CAN0.n()[0].psri().ptr(), 4, 1 << 7);
+
+    // psr0 and cccr0 for restart_after_bus_off, INIT has been set by the hardware
+    report.expect_read(CAN0.n()[0].psri().ptr(), 4, 1 << 7);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+
+    let mut changes = Vec::new();
+    node.handle_error_state_changes(|change| changes.push(change));
+    assert_eq!(changes, [ErrorStateChange::BusOff(true)]);
+
+    // The BO flag is cleared, then INIT is cleared
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_operating_modes() {
+    for (name, mode) in [
+        ("internal_loopback", OperatingMode::InternalLoopback),
+        ("external_loopback", OperatingMode::ExternalLoopback),
+        ("bus_monitoring", OperatingMode::BusMonitoring),
+        ("restricted", OperatingMode::Restricted),
+    ] {
+        let report = Report::new();
+        let mut can_module = enable_can0(&report);
+        report.take_log();
+
+        expect_take_node0(&report);
+
+        // cccr0 for set_operating_mode, INIT and CCE are set
+        report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b11);
+
+        // test0 for set_loopback_mode, only written when CCCR.TEST is set
+        if let OperatingMode::InternalLoopback | OperatingMode::ExternalLoopback = mode {
+            report.expect_read(CAN0.n()[0].testi().ptr(), 4, 0b0);
+        }
+
+        let config = NodeConfig {
+            operating_mode: mode,
+            ..node_config()
+        };
+        let _node = can_module
+            .take_node(Node0, config)
+            .expect("Cannot take can node");
+
+        insta::assert_snapshot!(format!("operating_mode_{name}"), report.take_log());
+    }
+}
+
+#[test]
+fn test_handle_rx_interrupt() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+    report.take_log();
+
+    let queue = RxQueue::<4>::new();
+
+    // ir0 with RF0N, TC and BO set, only RF0N is cleared
+    report.expect_read(CAN0.n()[0].iri().ptr(), 4, (1 << 25) | (1 << 9) | 1);
+
+    // rxf0s0 and rxf1s0 for get_rx_element, both FIFOs are empty
+    report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].rx().rxf1si().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].rx().rxf1si().ptr(), 4, 0b0);
+
+    // ndat1 and ndat2 without new data in the dedicated Rx buffers
+    report.expect_read(CAN0.n()[0].ndat1i().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].ndat2i().ptr(), 4, 0b0);
+
+    let flags = node.handle_rx_interrupt(&queue);
+    assert!(flags.contains(Interrupt::TransmissionCompleted));
+    assert!(flags.contains(Interrupt::BusOffStatus));
+    assert!(queue.is_empty());
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_full_rx_fifo_in_overwrite_mode() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Overwrite,
+    );
+    report.take_log();
+
+    // rxf0s0 with F0F set, 4 messages, get index 2 and put index 2, then rxf0a0 to drop the
+    // oldest message
+    report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, 0x0102_0204);
+    report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, 0x0102_0204);
+    report.expect_read(CAN0.n()[0].rx().rxf0ai().ptr(), 4, 0b0);
+
+    // The three remaining messages are read from elements 3, 0 and 1
+    expect_read_rx_fifo0_element(&report, 0x0002_0303, 3, 0x121);
+    expect_read_rx_fifo0_element(&report, 0x0002_0002, 0, 0x122);
+    expect_read_rx_fifo0_element(&report, 0x0002_0101, 1, 0x123);
+
+    // rxf0s0 with an empty FIFO
+    for _ in 0..3 {
+        report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, 0x0002_0200);
+    }
+
+    let mut ids = Vec::new();
+    let result = node.drain_fifo(ReadFrom::RxFifo0, |frame| ids.push(frame.id()));
+    assert_eq!(
+        result,
+        Ok(DrainReport {
+            frames: 3,
+            message_lost: true
+        })
+    );
+    let standard = |id| embedded_can::Id::Standard(StandardId::new(id).unwrap());
+    assert_eq!(ids, [standard(0x121), standard(0x122), standard(0x123)]);
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_drain_fifo_with_message_lost() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+    report.take_log();
+
+    // rxf0s0 with RF0L set, the flag is cleared in ir0
+    report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, 1 << 25);
+
+    // rxf0s0 with RF0L cleared and an empty FIFO
+    report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, 0b0);
+
+    let mut count = 0;
+    let result = node.drain_fifo(ReadFrom::RxFifo0, |_| count += 1);
+    assert_eq!(
+        result,
+        Ok(DrainReport {
+            frames: 0,
+            message_lost: true
+        })
+    );
+    assert_eq!(count, 0);
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_transmit_remote_request() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+    report.take_log();
+
+    let id = MessageId {
+        data: 0x123,
+        length: MessageIdLength::Standard,
+    };
+
+    // Remote frames are classic CAN frames, the Tx FIFO is not accessed
+    assert_eq!(
+        node.transmit_remote_request(id, 12),
+        Err(TransmitError::InvalidDataLength)
+    );
+
+    // txfqs0 with TFQF set, the put index must not be used
+    report.expect_read(CAN0.n()[0].tx().txfqsi().ptr(), 4, 1 << 21);
+    assert_eq!(
+        node.transmit_remote_request(id, 4),
+        Err(TransmitError::Busy)
+    );
+
+    // Only the dedicated Tx buffers can hold an answer
+    let buffer = TxBufferId::try_from(0).unwrap();
+    assert_eq!(
+        node.send_remote_answer(buffer),
+        Err(TransmitError::InvalidBuffer)
+    );
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_answer_remote_request() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+
+    let node = setup_dedicated_tx_buffers0(&report, node, DataFieldSize::_8);
+    report.take_log();
+
+    let request = RxBufferId::try_from(0).unwrap();
+    let answer = TxBufferId::try_from(1).unwrap();
+
+    // ndat1 without new data in the request buffer, the answer is not sent
+    report.expect_read(CAN0.n()[0].ndat1i().ptr(), 4, 0b0);
+
+    report.comment("answer_remote_request");
+    assert_eq!(node.answer_remote_request(request, answer), Ok(false));
+
+    // txbrp0 without pending request, then txbar0 to request the answer
+    report.expect_read(CAN0.n()[0].tx().txbrpi().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbari().ptr(), 4, 0b0);
+
+    report.comment("send_remote_answer");
+    assert_eq!(node.send_remote_answer(answer), Ok(()));
+
+    // txbrp0 with the answer pending, it also serves this request
+    report.expect_read(CAN0.n()[0].tx().txbrpi().ptr(), 4, 0b10);
+
+    report.comment("send_remote_answer while pending");
+    assert_eq!(node.send_remote_answer(answer), Ok(()));
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_stop_clock_and_release_node() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    let node = take_configured_node0(
+        &report,
+        &mut can_module,
+        node_config(),
+        RxFifoMode::Blocking,
+    );
+    report.take_log();
+
+    // cccr0 for request_clock_stop, CSA and INIT are set once the transfers are completed
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1_1001);
+
+    report.comment("stop_clock");
+    let node = node.stop_clock();
+
+    // cccr0 for clear_clock_stop_request then clear_initialization
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1_1001);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+
+    report.comment("resume_clock");
+    let node = node.resume_clock();
+
+    // cccr0 for request_clock_stop
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1_1001);
+
+    report.comment("stop_clock");
+    let node = node.stop_clock();
+
+    // cccr0 for clear_clock_stop_request then set_initialization, the node stays stopped
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1_1001);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+
+    report.comment("release_node");
+    can_module.release_node(node);
+
+    let log = report.take_log();
+
+    // The released node can be taken again
+    expect_take_node0(&report);
+    assert!(can_module.take_node(Node0, node_config()).is_ok());
+
+    insta::assert_snapshot!(log);
+}
+
+#[test]
+fn test_take_node_with_ram_layout() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    report.take_log();
+
+    let layout = NodeRamLayout {
+        standard_filter_list_start_address: 0,
+        extended_filter_list_start_address: 0,
+        rx_fifo0_start_address: 0,
+        rx_fifo1_start_address: 0,
+        rx_buffers_start_address: 0,
+        tx_event_fifo_start_address: 0x100,
+        tx_buffers_start_address: 0x108,
+        end_address: 0x128,
+        requirements: NodeRamRequirements {
+            tx_event_fifo_size: 1,
+            tx_buffers: 2,
+            ..NodeRamRequirements::UNUSED
+        },
+    };
+
+    // A misaligned layout is rejected before any register access, the node stays available
+    let config = NodeConfig {
+        ram_layout: Some(NodeRamLayout {
+            tx_buffers_start_address: 0x10A,
+            ..layout
+        }),
+        ..node_config()
+    };
+    assert!(matches!(
+        can_module.take_node(Node0, config),
+        Err(TakeNodeError::InvalidRamLayout)
+    ));
+    assert_eq!(report.take_log().to_string(), "");
+
+    expect_take_node0(&report);
+    let config = NodeConfig {
+        ram_layout: Some(layout),
+        ..node_config()
+    };
+    let mut node = can_module
+        .take_node(Node0, config)
+        .expect("Cannot take can node");
+    report.take_log();
+
+    // A Tx configuration which does not use the start addresses of the layout, or which needs
+    // more Tx buffers than reserved, is rejected before any register access
+    let tx_config = TxConfig {
+        mode: TxMode::Fifo,
+        dedicated_tx_buffers_number: 0,
+        fifo_queue_size: 2,
+        buffer_data_field_size: DataFieldSize::_8,
+        event_fifo_size: 1,
+        tx_event_fifo_start_address: 0x400,
+        tx_buffers_start_address: 0x440,
+    };
+    assert_eq!(
+        node.setup_tx(&tx_config),
+        Err(ConfigError::RamLayoutMismatch)
+    );
+    let tx_config = TxConfig {
+        tx_event_fifo_start_address: layout.tx_event_fifo_start_address,
+        tx_buffers_start_address: layout.tx_buffers_start_address,
+        ..tx_config
+    };
+    let too_many_buffers = TxConfig {
+        fifo_queue_size: 3,
+        ..tx_config
+    };
+    assert_eq!(
+        node.setup_tx(&too_many_buffers),
+        Err(ConfigError::RamLayoutMismatch)
+    );
+    assert_eq!(report.take_log().to_string(), "");
+
+    // txesc0, txbc0, txbtie0, txefc0 and cccr0 for setup_tx
+    report.expect_read(CAN0.n()[0].tx().txesci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0x108);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0x4000_0108);
+    report.expect_read(CAN0.n()[0].tx().txbtiei().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbtiei().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].tx().txefci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txefci().ptr(), 4, 0x100);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b11);
+
+    assert_eq!(node.setup_tx(&tx_config), Ok(()));
+
+    // The layout reserves no Rx FIFO 0 element and no standard filter
+    let rx_config = RxConfig {
+        mode: RxMode::Fifo0,
+        buffer_data_field_size: DataFieldSize::_8,
+        fifo0_data_field_size: DataFieldSize::_8,
+        fifo1_data_field_size: DataFieldSize::_8,
+        fifo0_operating_mode: RxFifoMode::Blocking,
+        fifo1_operating_mode: RxFifoMode::Blocking,
+        fifo0_watermark_level: 0,
+        fifo1_watermark_level: 0,
+        fifo0_size: 4,
+        fifo1_size: 0,
+        rx_fifo0_start_address: layout.rx_fifo0_start_address,
+        rx_fifo1_start_address: layout.rx_fifo1_start_address,
+        rx_buffers_start_address: layout.rx_buffers_start_address,
+    };
+    assert_eq!(
+        node.setup_rx(rx_config),
+        Err(ConfigError::RamLayoutMismatch)
+    );
+    assert_eq!(
+        node.setup_standard_filter_list(0, 1),
+        Err(FilterError::InvalidListSize)
+    );
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+#[test]
+fn test_set_bit_timing_standard_disables_tdc() {
+    let report = Report::new();
+    let mut can_module = enable_can0(&report);
+    expect_take_node0(&report);
+    let mut node = can_module
+        .take_node(Node0, node_config())
+        .expect("Cannot take can node");
+    report.take_log();
+
+    expect_get_mcan_frequency(&report);
+
+    // nbtp0 for set_nominal_bit_timing
+    report.expect_read(
+        CAN0.n()[0].nbtpi().ptr(),
+        4,
+        0b110_0000_0000_0000_1010_0000_0011,
+    );
+
+    // dbtp0 with TDC set by a previous FD configuration
+    report.expect_read(CAN0.n()[0].dbtpi().ptr(), 4, 0x0080_0A33);
+
+    // cccr0 with FDOE and BRSE set by a previous FD configuration
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0x303);
+
+    node.set_bit_timing(
+        FrameMode::Standard,
+        &node_config().baud_rate,
+        &FastBitTimingConfig::default(),
+        &TransceiverDelayCompensation::default(),
+    )
+    .expect("Cannot set bit timing");
+
+    insta::assert_snapshot!(report.take_log());
+}
+
+/// Enable CAN0
+fn enable_can0(report: &Report) -> Module<Module0, pac::can0::Can0, Enabled> {
+    // clear_cpu_endinit
+    report.expect_read(SCU.wdtcpu()[0].wdtcpuycon0().ptr(), 4, 0b11);
+
+    // clc read
+    report.expect_read(CAN0.clc().ptr(), 4, 0b0);
+
+    // set_cpu_endinit
+    report.expect_read(SCU.wdtcpu()[0].wdtcpuycon0().ptr(), 4, 0b10);
+
+    Module::new(Module0).enable()
+}
+
+/// Configuration of the nodes taken by the tests, 1 Mbit/s in classic CAN mode
+fn node_config() -> NodeConfig {
+    NodeConfig {
+        baud_rate: BitTimingConfig::Auto(AutoBitTiming {
+            baud_rate: 1_000_000,
+            sample_point: 8_000,
+            sync_jump_width: 3,
+        }),
+        ..Default::default()
+    }
+}
+
+/// Expect the reads of `take_node` for node 0 with [`node_config`], up to the global filter
+/// configuration
+fn expect_take_node0(report: &Report) {
+    // mcr read for set clock source
+    report.expect_read(CAN0.mcr().ptr(), 4, 0b0);
+    report.expect_read(CAN0.mcr().ptr(), 4, 0b11);
+
+    // cccr0 for enable configuration change
+    for value in [0b1, 0b1, 0b1, 0b1, 0b0, 0b0, 0b1, 0b1] {
+        report.expect_read(CAN0.n()[0].cccri().ptr(), 4, value);
+    }
+
+    expect_get_mcan_frequency(report);
+
+    // nbtp0 for set_nominal_bit_timing
+    report.expect_read(
+        CAN0.n()[0].nbtpi().ptr(),
+        4,
+        0b110_0000_0000_0000_1010_0000_0011,
+    );
+
+    // gfc0 for set_global_filter_configuration
+    report.expect_read(CAN0.n()[0].gfci().ptr(), 4, 0b0);
+}
+
+/// Take node 0 with a Tx FIFO of two buffers and an Rx FIFO 0 of four elements, then lock its
+/// configuration. `config` must not access more registers than [`node_config`].
+fn take_configured_node0(
+    report: &Report,
+    can_module: &mut Module<Module0, pac::can0::Can0, Enabled>,
+    config: NodeConfig,
+    rx_fifo0_mode: RxFifoMode,
+) -> Node<pac::can0::N, pac::can0::Can0, Node0, Configured> {
+    expect_take_node0(report);
+
+    let mut node = can_module
+        .take_node(Node0, config)
+        .expect("Cannot take can node");
+
+    // txesc0 for set_tx_buffer_data_field_size for setup_tx
+    report.expect_read(CAN0.n()[0].tx().txesci().ptr(), 4, 0b0);
+
+    // txbc0 for set_tx_buffer_start_address, set_transmit_fifo_queue_mode and
+    // set_transmit_fifo_queue_size for setup_tx
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b100_0100_0000);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b100_0100_0000);
+
+    // txbtie0 for enable_tx_buffer_transmission_interrupt for setup_tx
+    report.expect_read(CAN0.n()[0].tx().txbtiei().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbtiei().ptr(), 4, 0b1);
+
+    // txefc0 for set_tx_event_fifo_start_address and set_tx_event_fifo_size for setup_tx
+    report.expect_read(CAN0.n()[0].tx().txefci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txefci().ptr(), 4, 0b100_0000_0000);
+
+    // cccr0 for set_frame_mode for setup_tx
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b11);
+
+    node.setup_tx(&TxConfig {
+        mode: TxMode::Fifo,
+        dedicated_tx_buffers_number: 0,
+        fifo_queue_size: 2,
+        buffer_data_field_size: DataFieldSize::_8,
+        event_fifo_size: 1,
+        tx_event_fifo_start_address: 0x400,
+        tx_buffers_start_address: 0x440,
+    })
+    .expect("Cannot set up Tx");
+
+    // rxesc0 for set_rx_fifo0_data_field_size for setup_rx
+    report.expect_read(CAN0.n()[0].rx().rxesci().ptr(), 4, 0b0);
+
+    // rxf0c0 for set_rx_fifo0_start_address, set_rx_fifo0_size, set_rx_fifo0_operating_mode
+    // and set_rx_fifo0_watermark_level for setup_rx
+    report.expect_read(CAN0.n()[0].rx().rxf0ci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].rx().rxf0ci().ptr(), 4, 0b1_0000_0000);
+    report.expect_read(
+        CAN0.n()[0].rx().rxf0ci().ptr(),
+        4,
+        0b100_0000_0001_0000_0000,
+    );
+    report.expect_read(
+        CAN0.n()[0].rx().rxf0ci().ptr(),
+        4,
+        0b100_0000_0001_0000_0000,
+    );
+
+    // cccr0 for set_frame_mode for setup_rx
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b11);
+
+    node.setup_rx(RxConfig {
+        mode: RxMode::Fifo0,
+        buffer_data_field_size: DataFieldSize::_8,
+        fifo0_data_field_size: DataFieldSize::_8,
+        fifo1_data_field_size: DataFieldSize::_8,
+        fifo0_operating_mode: rx_fifo0_mode,
+        fifo1_operating_mode: RxFifoMode::Blocking,
+        fifo0_watermark_level: 0,
+        fifo1_watermark_level: 0,
+        fifo0_size: 4,
+        fifo1_size: 0,
+        rx_fifo0_start_address: 0x100,
+        rx_fifo1_start_address: 0x200,
+        rx_buffers_start_address: 0x300,
+    })
+    .expect("Cannot set up Rx");
+
+    // cccr0 for disable configuration change
+    for _ in 0..4 {
+        report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+    }
+
+    node.lock_configuration()
+}
+
+/// Unlock the configuration of node 0 and replace its Tx FIFO with two dedicated Tx buffers
+fn setup_dedicated_tx_buffers0(
+    report: &Report,
+    node: Node<pac::can0::N, pac::can0::Can0, Node0, Configured>,
+    data_field_size: DataFieldSize,
+) -> Node<pac::can0::N, pac::can0::Can0, Node0, Configured> {
+    // cccr0 for enable_configuration_change
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b1);
+
+    let mut node = node.unlock_configuration();
+
+    // txesc0, txbc0 and txbtie0 for two dedicated Tx buffers, txefc0 and cccr0 for setup_tx
+    report.expect_read(CAN0.n()[0].tx().txesci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbci().ptr(), 4, 0b100_0100_0000);
+    report.expect_read(CAN0.n()[0].tx().txbtiei().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txbtiei().ptr(), 4, 0b1);
+    report.expect_read(CAN0.n()[0].tx().txefci().ptr(), 4, 0b0);
+    report.expect_read(CAN0.n()[0].tx().txefci().ptr(), 4, 0b100_0000_0000);
+    report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b11);
+
+    node.setup_tx(&TxConfig {
+        mode: TxMode::DedicatedBuffers,
+        dedicated_tx_buffers_number: 2,
+        fifo_queue_size: 0,
+        buffer_data_field_size: data_field_size,
+        event_fifo_size: 1,
+        tx_event_fifo_start_address: 0x400,
+        tx_buffers_start_address: 0x440,
+    })
+    .expect("Cannot set up Tx");
+
+    // cccr0 for disable configuration change
+    for _ in 0..4 {
+        report.expect_read(CAN0.n()[0].cccri().ptr(), 4, 0b0);
+    }
+
+    node.lock_configuration()
+}
+
+/// Expect the reads of one message read from Rx FIFO 0 in overwrite mode: rxf0s0 with `status`
+/// until the frame is released, rxesc0, then R0 and R1 of element `index` for a standard data
+/// frame without data
+fn expect_read_rx_fifo0_element(report: &Report, status: u32, index: usize, id: u32) {
+    // rxf0s0 for the full, message lost, fill level and get index checks, then rxesc0
+    for _ in 0..4 {
+        report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, status);
+    }
+    report.expect_read(CAN0.n()[0].rx().rxesci().ptr(), 4, 0b0);
+
+    // Elements of 16 bytes (8 header and 8 data bytes) from 0x100
+    let r0 = 0xF020_0100usize + index * 16;
+    let r1 = r0 + 4;
+    for address in [r0, r0, r0, r0, r1, r1, r0, r1, r1] {
+        let value = if address == r0 { id << 18 } else { 0x0 };
+        report.expect_read(address, 4, value);
+    }
+
+    // rxf0s0 to check that the element has not been overwritten, then rxf0a0 to release it
+    report.expect_read(CAN0.n()[0].rx().rxf0si().ptr(), 4, status);
+    report.expect_read(CAN0.n()[0].rx().rxf0ai().ptr(), 4, 0b0);
+}
+
+/// Expect the reads of the clock registers done by `get_mcan_frequency`
+fn expect_get_mcan_frequency(report: &Report) {
+    // ccucon1, ccucon0, syspllcon0, perpllcon0, perpllcon1 and ccucon1
+    report.expect_read(
+        SCU.ccucon1().ptr(),
+        4,
+        0b0010_0001_0001_0001_0000_0010_0001_0010,
+    );
+    report.expect_read(
+        SCU.ccucon0().ptr(),
+        4,
+        0b0001_0111_0010_0011_0000_0001_0001_0011,
+    );
+    report.expect_read(
+        SCU.syspllcon0().ptr(),
+        4,
+        0b0100_0000_0000_0001_0011_1010_0000_0000,
+    );
+    report.expect_read(SCU.perpllcon0().ptr(), 4, 0b1_0011_1111_0000_0000);
+    report.expect_read(SCU.perpllcon1().ptr(), 4, 0b1_0000_0001);
+    report.expect_read(
+        SCU.ccucon1().ptr(),
+        4,
+        0b10_0001_0001_0001_0000_0010_0001_0010,
+    );
+}